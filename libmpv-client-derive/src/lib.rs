@@ -0,0 +1,400 @@
+//! Derive macros for [`libmpv-client`](https://docs.rs/libmpv-client).
+//!
+//! Don't depend on this crate directly. Enable the `derive` feature of `libmpv-client` instead,
+//! which re-exports [`MpvFormat`](derive@MpvFormat), [`MpvRecv`](derive@MpvRecv) and [`MpvSend`](derive@MpvSend)
+//! and documents their attributes on `FromNode`,
+//! as well as the [`cplugin`](macro@cplugin) attribute.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, Generics, Ident, ItemFn, LitStr};
+
+/// Implement `MpvFormat` with `Format::NODE`, which `MpvRecv` and `MpvSend` require.
+#[proc_macro_derive(MpvFormat)]
+pub fn derive_mpv_format(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_format(&input).into()
+}
+
+/// Implement `FromNode` and `MpvRecv` through `Format::NODE`.
+#[proc_macro_derive(MpvRecv, attributes(mpv))]
+pub fn derive_mpv_recv(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_recv(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Implement `ToNode` and `MpvSend` through `Format::NODE`.
+#[proc_macro_derive(MpvSend, attributes(mpv))]
+pub fn derive_mpv_send(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_send(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Generate `mpv_open_cplugin` calling the annotated `fn(Handle) -> Result<()>`.
+#[proc_macro_attribute]
+pub fn cplugin(args: TokenStream, input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as ItemFn);
+    expand_cplugin(args.into(), &input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// A naming convention for `#[mpv(rename_all = "...")]`.
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Kebab,
+    Snake,
+    Camel,
+    Pascal,
+    Lower,
+    Upper,
+    ScreamingSnake,
+}
+
+impl RenameRule {
+    fn from_lit(lit: &LitStr) -> syn::Result<Self> {
+        Ok(match lit.value().as_str() {
+            "kebab-case" => RenameRule::Kebab,
+            "snake_case" => RenameRule::Snake,
+            "camelCase" => RenameRule::Camel,
+            "PascalCase" => RenameRule::Pascal,
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            _ => return Err(syn::Error::new(lit.span(), "unknown rename rule, expected one of \"kebab-case\", \"snake_case\", \"camelCase\", \
+                \"PascalCase\", \"lowercase\", \"UPPERCASE\" or \"SCREAMING_SNAKE_CASE\"")),
+        })
+    }
+
+    /// Apply the rule to a Rust identifier, which may be in snake_case or PascalCase.
+    fn apply(self, ident: &Ident) -> String {
+        let words = split_words(&ident.unraw().to_string());
+
+        let capitalize = |word: &String| {
+            let mut chars = word.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+        };
+
+        match self {
+            RenameRule::Kebab => words.join("-"),
+            RenameRule::Snake => words.join("_"),
+            RenameRule::Camel => words.first().cloned().into_iter().chain(words.iter().skip(1).map(capitalize)).collect(),
+            RenameRule::Pascal => words.iter().map(capitalize).collect(),
+            RenameRule::Lower => words.concat(),
+            RenameRule::Upper => words.concat().to_uppercase(),
+            RenameRule::ScreamingSnake => words.join("_").to_uppercase(),
+        }
+    }
+}
+
+/// Split an identifier into lowercase words at underscores and case changes, keeping acronyms together (`HTTPProxy` is `http`, `proxy`).
+fn split_words(ident: &str) -> Vec<String> {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if !prev.is_uppercase() || next_is_lower {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+
+        word.extend(c.to_lowercase());
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// The `#[mpv(...)]` attributes of a container.
+struct ContainerAttrs {
+    rename_all: RenameRule,
+    /// Where `rename_all` was given, if it was.
+    rename_all_span: Option<Span>,
+}
+
+impl ContainerAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut result = Self { rename_all: RenameRule::Kebab, rename_all_span: None };
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("mpv")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    result.rename_all = RenameRule::from_lit(&meta.value()?.parse()?)?;
+                    result.rename_all_span = Some(meta.path.span());
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown attribute, expected `rename_all`"))
+                }
+            })?;
+        }
+
+        Ok(result)
+    }
+}
+
+/// The `#[mpv(...)]` attributes of a field or variant.
+#[derive(Default)]
+struct ItemAttrs {
+    rename: Option<String>,
+    default: bool,
+    skip: bool,
+}
+
+impl ItemAttrs {
+    fn parse(attrs: &[Attribute], allow_defaults: bool) -> syn::Result<Self> {
+        let mut result = Self::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("mpv")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    result.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if allow_defaults && meta.path.is_ident("default") {
+                    result.default = true;
+                } else if allow_defaults && meta.path.is_ident("skip") {
+                    result.skip = true;
+                } else if allow_defaults {
+                    return Err(meta.error("unknown attribute, expected `rename`, `default` or `skip`"));
+                } else {
+                    return Err(meta.error("unknown attribute, expected `rename`"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(result)
+    }
+}
+
+/// A field of a struct with named fields.
+struct NamedField {
+    ident: Ident,
+    key: String,
+    attrs: ItemAttrs,
+}
+
+/// The supported shapes of a deriving type.
+enum Shape {
+    /// A struct with named fields, converted to and from a map.
+    Map(Vec<NamedField>),
+    /// A struct with exactly one unnamed field, converted transparently.
+    Newtype,
+    /// A struct with several unnamed fields, converted to and from an array.
+    Tuple(usize),
+    /// An enum with only unit variants, converted to and from a string.
+    Enum(Vec<(Ident, String)>),
+}
+
+impl Shape {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let container = ContainerAttrs::parse(&input.attrs)?;
+
+        match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => fields.named.iter()
+                    .map(|field| {
+                        let ident = field.ident.clone().expect("named field");
+                        let attrs = ItemAttrs::parse(&field.attrs, true)?;
+                        let key = attrs.rename.clone().unwrap_or_else(|| container.rename_all.apply(&ident));
+                        Ok(NamedField { ident, key, attrs })
+                    })
+                    .collect::<syn::Result<_>>()
+                    .map(Shape::Map),
+                Fields::Unnamed(fields) => {
+                    if let Some(span) = container.rename_all_span {
+                        return Err(syn::Error::new(span, "`rename_all` is only supported on structs with named fields and enums"));
+                    }
+                    for field in &fields.unnamed {
+                        let attrs = ItemAttrs::parse(&field.attrs, false)?;
+                        if attrs.rename.is_some() {
+                            return Err(syn::Error::new(field.span(), "`rename` is only supported on named fields"));
+                        }
+                    }
+                    Ok(if fields.unnamed.len() == 1 { Shape::Newtype } else { Shape::Tuple(fields.unnamed.len()) })
+                }
+                Fields::Unit => Err(syn::Error::new(input.ident.span(), "unit structs are not supported")),
+            },
+            Data::Enum(data) => data.variants.iter()
+                .map(|variant| {
+                    if !matches!(variant.fields, Fields::Unit) {
+                        return Err(syn::Error::new(variant.span(), "only enums with unit variants are supported"));
+                    }
+                    let attrs = ItemAttrs::parse(&variant.attrs, false)?;
+                    let name = attrs.rename.unwrap_or_else(|| container.rename_all.apply(&variant.ident));
+                    Ok((variant.ident.clone(), name))
+                })
+                .collect::<syn::Result<_>>()
+                .map(Shape::Enum),
+            Data::Union(_) => Err(syn::Error::new(input.ident.span(), "unions are not supported")),
+        }
+    }
+}
+
+/// Add `bound` to every type parameter of `generics`.
+fn add_bounds(generics: &Generics, bound: syn::TypeParamBound) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(bound.clone());
+    }
+    generics
+}
+
+fn expand_format(input: &DeriveInput) -> TokenStream2 {
+    let private = quote!(::libmpv_client::__private);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #private::DerivedFormat for #ident #ty_generics #where_clause {}
+    }
+}
+
+fn expand_recv(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let private = quote!(::libmpv_client::__private);
+    let ident = &input.ident;
+
+    let body = match Shape::parse(input)? {
+        Shape::Map(fields) => {
+            let fields = fields.iter().map(|NamedField { ident, key, attrs }| {
+                if attrs.skip {
+                    quote!(#ident: ::core::default::Default::default())
+                } else if attrs.default {
+                    quote!(#ident: #private::field_or_default(&mut map, #key)?)
+                } else {
+                    quote!(#ident: #private::field(&mut map, #key)?)
+                }
+            });
+            quote! {
+                let mut map = #private::expect_map(node)?;
+                ::core::result::Result::Ok(Self { #(#fields,)* })
+            }
+        }
+        Shape::Newtype => quote! {
+            #private::FromNode::from_node(node).map(Self)
+        },
+        Shape::Tuple(len) => {
+            let expected = format!("an array of length {len}");
+            let elements = (0..len).map(|i| quote!(#private::element(&mut elements, #i)?));
+            quote! {
+                let mut elements = #private::expect_array(node, #len, #expected)?;
+                ::core::result::Result::Ok(Self(#(#elements),*))
+            }
+        }
+        Shape::Enum(variants) => {
+            let expected = expected_variants(&variants);
+            let arms = variants.iter().map(|(variant, name)| quote!(#name => ::core::result::Result::Ok(Self::#variant)));
+            quote! {
+                let name = #private::expect_variant(node, #expected)?;
+                match name.as_str() {
+                    #(#arms,)*
+                    _ => ::core::result::Result::Err(#private::unknown_variant(&name, #expected)),
+                }
+            }
+        }
+    };
+
+    let bounded = add_bounds(&input.generics, parse_quote!(#private::FromNode));
+    let (impl_generics, ty_generics, where_clause) = bounded.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #private::FromNode for #ident #ty_generics #where_clause {
+            fn from_node(node: #private::Node) -> #private::Result<Self> {
+                #body
+            }
+        }
+
+        impl #impl_generics #private::DerivedRecv for #ident #ty_generics #where_clause {}
+    })
+}
+
+fn expand_send(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let private = quote!(::libmpv_client::__private);
+    let ident = &input.ident;
+
+    let body = match Shape::parse(input)? {
+        Shape::Map(fields) => {
+            let inserts = fields.iter().filter(|field| !field.attrs.skip).map(|NamedField { ident, key, .. }| {
+                quote!(#private::insert_field(&mut map, #key, &self.#ident);)
+            });
+            quote! {
+                let mut map = #private::NodeMap::new();
+                #(#inserts)*
+                #private::Node::Map(map)
+            }
+        }
+        Shape::Newtype => quote! {
+            #private::ToNode::to_node(&self.0)
+        },
+        Shape::Tuple(len) => {
+            let elements = (0..len).map(syn::Index::from).map(|i| quote!(#private::ToNode::to_node(&self.#i)));
+            quote! {
+                #private::Node::Array(::std::vec![#(#elements),*])
+            }
+        }
+        Shape::Enum(variants) => {
+            let arms = variants.iter().map(|(variant, name)| quote!(Self::#variant => #name));
+            quote! {
+                #private::Node::String(::std::string::String::from(match *self { #(#arms,)* }))
+            }
+        }
+    };
+
+    let bounded = add_bounds(&input.generics, parse_quote!(#private::ToNode));
+    let (impl_generics, ty_generics, where_clause) = bounded.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #private::ToNode for #ident #ty_generics #where_clause {
+            fn to_node(&self) -> #private::Node {
+                #body
+            }
+        }
+
+        impl #impl_generics #private::DerivedSend for #ident #ty_generics #where_clause {}
+    })
+}
+
+/// Describe the accepted names of an enum for conversion errors, such as ``one of `no`, `auto` ``.
+fn expected_variants(variants: &[(Ident, String)]) -> String {
+    let names: Vec<String> = variants.iter().map(|(_, name)| format!("`{name}`")).collect();
+    format!("one of {}", names.join(", "))
+}
+
+fn expand_cplugin(args: TokenStream2, input: &ItemFn) -> syn::Result<TokenStream2> {
+    if !args.is_empty() {
+        return Err(syn::Error::new(args.span(), "`#[cplugin]` takes no arguments"));
+    }
+
+    let sig = &input.sig;
+    if let Some(token) = &sig.asyncness {
+        return Err(syn::Error::new(token.span(), "the cplugin function can't be async"));
+    }
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(syn::Error::new(sig.generics.span(), "the cplugin function can't be generic"));
+    }
+    if sig.inputs.len() != 1 {
+        return Err(syn::Error::new(sig.inputs.span(), "the cplugin function must take a single `Handle`"));
+    }
+
+    let ident = &sig.ident;
+    Ok(quote! {
+        #input
+
+        #[unsafe(no_mangle)]
+        extern "C" fn mpv_open_cplugin(handle: *mut ::libmpv_client::mpv_handle) -> ::std::os::raw::c_int {
+            ::libmpv_client::__private::run_cplugin(handle, #ident)
+        }
+    })
+}
@@ -0,0 +1,401 @@
+//! A small builder for [ASS](https://aegisub.org/docs/latest/ass_tags/) markup, as accepted by mpv's OSD.
+//!
+//! Both the [`show-text`](https://mpv.io/manual/stable/#command-interface-show-text) and
+//! [`osd-overlay`](https://mpv.io/manual/stable/#command-interface-osd-overlay) commands take ASS markup.
+//! Building it by hand is fragile: user strings must be escaped so that `{` and `\` can't inject override tags,
+//! colors are written in BGR order with an inverted alpha, and `show-text` additionally needs the
+//! [`osd-ass-cc`](https://mpv.io/manual/stable/#command-interface-osd-ass-cc) control codes and `$` escaping.
+//!
+//! [`Ass`] takes care of all of this.
+//!
+//! # Example
+//! ```
+//!# use libmpv_client::*;
+//!# use libmpv_client::ass::{Alignment, Ass, Color, Drawing};
+//!#
+//!# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+//!#     let handle = Handle::from_ptr(ptr);
+//! let title: String = handle.get_property("media-title")?;
+//!
+//! let mut ass = Ass::new();
+//! ass.pos(20.0, 20.0)
+//!     .an(Alignment::TopLeft)
+//!     .font_size(28.0)
+//!     .color(Color::WHITE)
+//!     .bold(true)
+//!     .text("Now playing: ")
+//!     .bold(false)
+//!     .text(&title); // escaped, even if the title contains `{` or `\`
+//!
+//! // show-text needs the osd-ass-cc control codes, which to_show_text() adds.
+//! handle.command(&["show-text", &ass.to_show_text(), "2000"])?;
+//!
+//! // osd-overlay takes the plain ASS events.
+//! let mut overlay = Ass::new();
+//! overlay.pos(0.0, 0.0)
+//!     .color(Color::rgba(0, 0, 0, 128))
+//!     .draw(Drawing::new().rect(0.0, 0.0, 1280.0, 40.0));
+//!
+//! handle.command_node(node_map! {
+//!     ("name", "osd-overlay"),
+//!     ("id", 1),
+//!     ("format", "ass-events"),
+//!     ("data", overlay.as_str()),
+//! })?;
+//!#     Ok(())
+//!# }
+//! ```
+
+use std::fmt::Write;
+
+/// The zero-width no-break space, used to break up sequences that libass would otherwise interpret.
+const ZWNBSP: char = '\u{FEFF}';
+
+/// The control code which disables escaping of ASS sequences in `show-text`. See [`osd-ass-cc`](https://mpv.io/manual/stable/#command-interface-osd-ass-cc).
+pub const OSD_ASS_CC_START: &str = "${osd-ass-cc/0}";
+/// The control code which re-enables escaping of ASS sequences in `show-text`. See [`osd-ass-cc`](https://mpv.io/manual/stable/#command-interface-osd-ass-cc).
+pub const OSD_ASS_CC_END: &str = "${osd-ass-cc/1}";
+
+/// An RGBA color.
+///
+/// The alpha channel uses the usual convention of `255` being fully opaque. It is inverted when written as ASS,
+/// which uses `0` for fully opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    /// The red channel.
+    pub r: u8,
+    /// The green channel.
+    pub g: u8,
+    /// The blue channel.
+    pub b: u8,
+    /// The opacity, where `255` is fully opaque and `0` is fully transparent.
+    pub a: u8,
+}
+
+impl Color {
+    /// Opaque white.
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    /// Opaque black.
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    /// Opaque red.
+    pub const RED: Color = Color::rgb(255, 0, 0);
+    /// Opaque green.
+    pub const GREEN: Color = Color::rgb(0, 255, 0);
+    /// Opaque blue.
+    pub const BLUE: Color = Color::rgb(0, 0, 255);
+    /// Fully transparent.
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    /// Create an opaque [`Color`].
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+
+    /// Create a [`Color`] with the given opacity.
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// Create an opaque [`Color`] from a `0xRRGGBB` value.
+    pub const fn from_hex(rgb: u32) -> Self {
+        Color::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+
+    /// The color as an ASS color value, in `&HBBGGRR&` form.
+    pub fn to_ass_color(&self) -> String {
+        format!("&H{:02X}{:02X}{:02X}&", self.b, self.g, self.r)
+    }
+
+    /// The opacity as an ASS alpha value, in `&HAA&` form (where `00` is opaque).
+    pub fn to_ass_alpha(&self) -> String {
+        format!("&H{:02X}&", 255 - self.a)
+    }
+}
+
+/// The `\an` alignment of an event, laid out like a numpad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// `\an1`
+    BottomLeft = 1,
+    /// `\an2`
+    BottomCenter = 2,
+    /// `\an3`
+    BottomRight = 3,
+    /// `\an4`
+    MiddleLeft = 4,
+    /// `\an5`
+    Center = 5,
+    /// `\an6`
+    MiddleRight = 6,
+    /// `\an7`
+    TopLeft = 7,
+    /// `\an8`
+    TopCenter = 8,
+    /// `\an9`
+    TopRight = 9,
+}
+
+/// A builder for ASS markup.
+///
+/// Override tags are collected and written as a single `{...}` block in front of the next text run or drawing.
+/// Text passed to [`Ass::text()`] is always escaped; use [`Ass::raw()`] to append markup verbatim.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone, Default)]
+pub struct Ass {
+    buf: String,
+    tags: String,
+}
+
+impl Ass {
+    /// Create an empty [`Ass`] builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new ASS event.
+    ///
+    /// `osd-overlay` accepts multiple events separated by newlines, each with its own position and alignment.
+    /// Pending override tags are discarded, since they would not carry over into the new event anyway.
+    pub fn new_event(&mut self) -> &mut Self {
+        self.tags.clear();
+        if !self.buf.is_empty() {
+            self.buf.push('\n');
+        }
+        self
+    }
+
+    /// Append an arbitrary override tag, without the leading `\` (e.g. `"blur2"`).
+    ///
+    /// The tag is not escaped; braces, which would end or nest the override block, are removed.
+    pub fn tag(&mut self, tag: &str) -> &mut Self {
+        self.tags.push('\\');
+        self.tags.extend(tag.chars().filter(|&c| c != '}' && c != '{'));
+        self
+    }
+
+    /// Position the event at `(x, y)` with `\pos`. How the position is interpreted depends on [`Ass::an()`].
+    pub fn pos(&mut self, x: f64, y: f64) -> &mut Self {
+        self.tag(&format!("pos({x},{y})"))
+    }
+
+    /// Set the alignment of the event with `\an`.
+    pub fn an(&mut self, alignment: Alignment) -> &mut Self {
+        self.tag(&format!("an{}", alignment as u8))
+    }
+
+    /// Set the font size with `\fs`.
+    pub fn font_size(&mut self, size: f64) -> &mut Self {
+        self.tag(&format!("fs{size}"))
+    }
+
+    /// Set the font name with `\fn`.
+    ///
+    /// Characters which cannot appear in an override tag (`\`, `{` and `}`) are removed.
+    pub fn font(&mut self, name: &str) -> &mut Self {
+        let name: String = name.chars().filter(|&c| c != '\\').collect();
+        self.tag(&format!("fn{name}"))
+    }
+
+    /// Enable or disable bold text with `\b`.
+    pub fn bold(&mut self, enable: bool) -> &mut Self {
+        self.tag(if enable { "b1" } else { "b0" })
+    }
+
+    /// Enable or disable italic text with `\i`.
+    pub fn italic(&mut self, enable: bool) -> &mut Self {
+        self.tag(if enable { "i1" } else { "i0" })
+    }
+
+    /// Enable or disable underlined text with `\u`.
+    pub fn underline(&mut self, enable: bool) -> &mut Self {
+        self.tag(if enable { "u1" } else { "u0" })
+    }
+
+    /// Set the primary (fill) color and its opacity with `\1c` and `\1a`.
+    pub fn color(&mut self, color: Color) -> &mut Self {
+        self.tag(&format!("1c{}", color.to_ass_color()))
+            .tag(&format!("1a{}", color.to_ass_alpha()))
+    }
+
+    /// Set the border color and its opacity with `\3c` and `\3a`.
+    pub fn border_color(&mut self, color: Color) -> &mut Self {
+        self.tag(&format!("3c{}", color.to_ass_color()))
+            .tag(&format!("3a{}", color.to_ass_alpha()))
+    }
+
+    /// Set the shadow color and its opacity with `\4c` and `\4a`.
+    pub fn shadow_color(&mut self, color: Color) -> &mut Self {
+        self.tag(&format!("4c{}", color.to_ass_color()))
+            .tag(&format!("4a{}", color.to_ass_alpha()))
+    }
+
+    /// Set the opacity of all components at once with `\alpha`, where `255` is fully opaque.
+    pub fn alpha(&mut self, alpha: u8) -> &mut Self {
+        self.tag(&format!("alpha&H{:02X}&", 255 - alpha))
+    }
+
+    /// Set the border width with `\bord`.
+    pub fn border(&mut self, width: f64) -> &mut Self {
+        self.tag(&format!("bord{width}"))
+    }
+
+    /// Set the shadow depth with `\shad`.
+    pub fn shadow(&mut self, depth: f64) -> &mut Self {
+        self.tag(&format!("shad{depth}"))
+    }
+
+    /// Blur the edges with `\blur`.
+    pub fn blur(&mut self, strength: f64) -> &mut Self {
+        self.tag(&format!("blur{strength}"))
+    }
+
+    /// Scale the text or drawing with `\fscx` and `\fscy`, in percent.
+    pub fn scale(&mut self, x: f64, y: f64) -> &mut Self {
+        self.tag(&format!("fscx{x}")).tag(&format!("fscy{y}"))
+    }
+
+    /// Reset all styling to the defaults with `\r`.
+    pub fn reset(&mut self) -> &mut Self {
+        self.tag("r")
+    }
+
+    /// Append a run of text, escaped with [`escape()`].
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.flush_tags();
+        self.buf.push_str(&escape(text));
+        self
+    }
+
+    /// Append raw ASS markup verbatim. The caller is responsible for escaping.
+    pub fn raw(&mut self, markup: &str) -> &mut Self {
+        self.flush_tags();
+        self.buf.push_str(markup);
+        self
+    }
+
+    /// Append a vector [`Drawing`], wrapped in `{\p1}` and `{\p0}`.
+    pub fn draw(&mut self, drawing: &Drawing) -> &mut Self {
+        self.tag("p1");
+        self.flush_tags();
+        self.buf.push_str(drawing.as_str());
+        self.buf.push_str("{\\p0}");
+        self
+    }
+
+    /// The ASS markup built so far, suitable for `osd-overlay`.
+    ///
+    /// Override tags which were not followed by any text or drawing are not included.
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    /// The ASS markup built so far, prepared for `show-text`.
+    ///
+    /// `show-text` expands properties and escapes ASS by default, so `$` is escaped as `$$`,
+    /// and the markup is wrapped in [`OSD_ASS_CC_START`] and [`OSD_ASS_CC_END`].
+    pub fn to_show_text(&self) -> String {
+        format!("{OSD_ASS_CC_START}{}{OSD_ASS_CC_END}", self.buf.replace('$', "$$"))
+    }
+
+    fn flush_tags(&mut self) {
+        if !self.tags.is_empty() {
+            let _ = write!(self.buf, "{{{}}}", self.tags);
+            self.tags.clear();
+        }
+    }
+}
+
+/// Escape a string so that it is displayed literally by libass.
+///
+/// - `\` is followed by a zero-width no-break space, so that sequences such as `\N` are not interpreted.
+/// - `{` and `}` are escaped, so that they cannot open or close an override block.
+/// - Newlines are turned into `\N` (preceded by a zero-width no-break space, so that consecutive newlines aren't collapsed).
+/// - Leading spaces are turned into hard spaces, so that they aren't stripped.
+///
+/// This matches the escaping done by mpv's own Lua scripts.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut line_start = true;
+
+    for c in text.chars() {
+        match c {
+            '\\' => {
+                escaped.push('\\');
+                escaped.push(ZWNBSP);
+            }
+            '{' => escaped.push_str("\\{"),
+            '}' => escaped.push_str("\\}"),
+            '\n' => {
+                escaped.push(ZWNBSP);
+                escaped.push_str("\\N");
+                line_start = true;
+                continue;
+            }
+            ' ' if line_start => {
+                escaped.push_str("\\h");
+                continue;
+            }
+            c => escaped.push(c),
+        }
+        line_start = false;
+    }
+
+    escaped
+}
+
+/// A builder for an ASS vector drawing, as used between `{\p1}` and `{\p0}`.
+///
+/// Coordinates are in the drawing's own space; position the drawing with [`Ass::pos()`] and [`Ass::an()`].
+#[derive(Debug, Clone, Default)]
+pub struct Drawing {
+    buf: String,
+}
+
+impl Drawing {
+    /// Create an empty [`Drawing`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Close the current shape and move the cursor to `(x, y)` with `m`.
+    pub fn move_to(&mut self, x: f64, y: f64) -> &mut Self {
+        self.command(format_args!("m {x} {y}"))
+    }
+
+    /// Move the cursor to `(x, y)` without closing the current shape with `n`.
+    pub fn move_to_open(&mut self, x: f64, y: f64) -> &mut Self {
+        self.command(format_args!("n {x} {y}"))
+    }
+
+    /// Draw a line from the cursor to `(x, y)` with `l`.
+    pub fn line_to(&mut self, x: f64, y: f64) -> &mut Self {
+        self.command(format_args!("l {x} {y}"))
+    }
+
+    /// Draw a cubic Bézier curve from the cursor to `(x3, y3)`, with the control points `(x1, y1)` and `(x2, y2)`, with `b`.
+    pub fn bezier_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64) -> &mut Self {
+        self.command(format_args!("b {x1} {y1} {x2} {y2} {x3} {y3}"))
+    }
+
+    /// Draw an axis-aligned rectangle from `(x0, y0)` to `(x1, y1)` as a new shape.
+    pub fn rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64) -> &mut Self {
+        self.move_to(x0, y0)
+            .line_to(x1, y0)
+            .line_to(x1, y1)
+            .line_to(x0, y1)
+    }
+
+    /// The drawing commands built so far.
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    fn command(&mut self, args: std::fmt::Arguments) -> &mut Self {
+        if !self.buf.is_empty() {
+            self.buf.push(' ');
+        }
+        let _ = self.buf.write_fmt(args);
+        self
+    }
+}
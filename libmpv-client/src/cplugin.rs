@@ -0,0 +1,24 @@
+//! The runtime of the entry point generated by `#[cplugin]`.
+
+use std::ffi::c_int;
+use crate::*;
+
+/// Run `main` with the [`Handle`] at `ptr`, after the initial [`Handle::wait_event()`],
+/// and convert its result into the return value of `mpv_open_cplugin`. Panics are contained by [`panic`](crate::panic).
+pub fn run(ptr: *mut mpv_handle, main: impl FnOnce(Handle) -> Result<()>) -> c_int {
+    panic::guard("mpv_open_cplugin", ptr, || exit_code(Err(Error::Generic)), || {
+        let handle = Handle::from_ptr(ptr);
+        exit_code(match handle.wait_event(0.0) {
+            Ok(Event::Shutdown) => Ok(()),
+            Ok(_) => main(handle),
+            Err(e) => Err(e),
+        })
+    })
+}
+
+pub(crate) fn exit_code(result: Result<()>) -> c_int {
+    match result {
+        Ok(()) => 0,
+        Err(e) => c_int::from(&e),
+    }
+}
@@ -0,0 +1,323 @@
+//! An event loop which routes mpv's events to registered handlers, instead of matching on [`Handle::wait_event()`] by hand.
+//!
+//! See [`Dispatcher`].
+
+use std::collections::BTreeMap;
+use libmpv_client_sys::{mpv_event, mpv_event_id_MPV_EVENT_PROPERTY_CHANGE, mpv_event_property};
+use crate::*;
+use crate::event::{Hook, LogLevel, LogMessage};
+use crate::hooks::{self, OnLoad};
+use crate::observer::next_userdata;
+
+type PropertyHandler<'a> = Box<dyn FnMut(&Handle, PropertySource) -> Result<()> + 'a>;
+type EventHandler<'a> = Box<dyn FnMut(&Handle, &Event) -> Result<()> + 'a>;
+type HookHandler<'a> = Box<dyn FnMut(&Handle, &Hook) -> Result<()> + 'a>;
+type MessageHandler<'a> = Box<dyn FnMut(&Handle, &[String]) -> Result<()> + 'a>;
+type ShutdownHandler<'a> = Box<dyn FnMut(&Handle) -> Result<()> + 'a>;
+
+/// Where a property handler gets its value from.
+enum PropertySource<'e> {
+    /// An [`Event::PropertyChange`] sent by mpv.
+    Event(&'e mpv_event_property),
+    /// A synthetic change after an [`Event::QueueOverflow`], read with [`Handle::get_property()`].
+    Fetch(&'e str),
+}
+
+/// Identifies a handler registered with a [`Dispatcher`], so that it can be removed with [`Dispatcher::remove()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HandlerId(pub(crate) u64);
+
+enum Handler<'a> {
+    Property(String, PropertyHandler<'a>),
+    Event(EventId, EventHandler<'a>),
+    Hook(HookHandler<'a>),
+    /// A removed hook handler. mpv can't unregister hooks, so its events are still received and continued right away.
+    RemovedHook,
+    ClientMessage(String, MessageHandler<'a>),
+    Shutdown(ShutdownHandler<'a>),
+}
+
+/// An event loop which owns a [`Handle`] and routes its events to registered handlers.
+///
+/// Handlers receive the [`Handle`] as their first argument, and run in order of registration.
+/// The `userdata` values of observed properties and hooks are allocated by the [`Dispatcher`], and events with other `userdata`
+/// values are ignored, so don't use [`Handle::observe_property()`] or [`Handle::hook_add()`] directly on [`Dispatcher::handle()`].
+///
+/// An [`Err`] returned by a handler stops the current [`Dispatcher::poll()`] or [`Dispatcher::run()`] and is returned from it.
+/// The [`Dispatcher`] stays usable, so the loop can be resumed.
+///
+/// If events are not read quickly enough, mpv drops some and sends an [`Event::QueueOverflow`], after which observed values may be stale.
+/// These are counted (see [`Dispatcher::overflow_count()`]), and can be recovered from with [`Dispatcher::set_resync_on_overflow()`].
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::dispatch::Dispatcher;
+///#
+/// #[unsafe(no_mangle)]
+/// extern "C" fn mpv_open_cplugin(ptr: *mut mpv_handle) -> std::os::raw::c_int {
+///     let mut dispatcher = Dispatcher::new(Handle::from_ptr(ptr));
+///
+///     let result = (|| {
+///         dispatcher.on_property::<f64>("volume", |_handle, volume| {
+///             println!("volume is now {volume:?}");
+///             Ok(())
+///         })?;
+///         dispatcher.on_hook("on_load", 0, |handle, _hook| {
+///             println!("loading {}", handle.get_property::<String>("stream-open-filename")?);
+///             Ok(())
+///         })?;
+///         dispatcher.on_client_message("toggle-pause", |handle, _args| {
+///             handle.command(&["cycle", "pause"])
+///         });
+///         dispatcher.on_shutdown(|_handle| {
+///             println!("Goodbye from Rust!");
+///             Ok(())
+///         });
+///
+///         // Handle events until mpv shuts down.
+///         dispatcher.run()
+///     })();
+///
+///     if result.is_ok() { 0 } else { -1 }
+/// }
+/// ```
+pub struct Dispatcher<'a> {
+    handle: Handle,
+    handlers: BTreeMap<u64, Handler<'a>>,
+    shutdown: bool,
+    resync_on_overflow: bool,
+    overflow_count: u64,
+}
+
+impl<'a> Dispatcher<'a> {
+    /// Create a [`Dispatcher`] for the events of `handle`, without any handlers.
+    pub fn new(handle: Handle) -> Self {
+        Self {
+            handle,
+            handlers: BTreeMap::new(),
+            shutdown: false,
+            resync_on_overflow: false,
+            overflow_count: 0,
+        }
+    }
+
+    /// The [`Handle`] whose events are dispatched.
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Whether to re-read all observed properties after an [`Event::QueueOverflow`], which is off by default.
+    ///
+    /// Each [`Dispatcher::on_property()`] handler is then called with the current value read with [`Handle::get_property()`],
+    /// as if mpv had sent a change, since the real changes may have been dropped. Handlers registered with [`Dispatcher::on_event()`] aren't affected.
+    pub fn set_resync_on_overflow(&mut self, enable: bool) {
+        self.resync_on_overflow = enable;
+    }
+
+    /// The number of [`Event::QueueOverflow`] events received so far.
+    ///
+    /// If this keeps growing, events are not read quickly enough, for example because handlers take too long.
+    pub fn overflow_count(&self) -> u64 {
+        self.overflow_count
+    }
+
+    /// Store `handler` under `id`, which is also the `userdata` of its property or hook. Ids are allocated in increasing order.
+    fn register(&mut self, id: u64, handler: Handler<'a>) -> HandlerId {
+        self.handlers.insert(id, handler);
+        HandlerId(id)
+    }
+
+    /// Observe the property `name` as a `T`, and call `handler` with its value whenever it changes, as well as once initially.
+    ///
+    /// The value is [`None`] if the property is unavailable. See [`Handle::observe_property()`] for details on how changes are reported.
+    pub fn on_property<T: MpvRecv>(&mut self, name: &str, mut handler: impl FnMut(&Handle, Option<T>) -> Result<()> + 'a) -> Result<HandlerId> {
+        let id = next_userdata();
+        self.handle.observe_property(name, T::MPV_FORMAT, id)?;
+
+        Ok(self.register(id, Handler::Property(name.to_string(), Box::new(move |handle, source| {
+            let value = match source {
+                // The data is only in the requested format if the property is available.
+                PropertySource::Event(property) if property.format == T::MPV_FORMAT.0 && !property.data.is_null() => {
+                    Some(unsafe { T::from_ptr(property.data)? })
+                }
+                PropertySource::Event(_) => None,
+                PropertySource::Fetch(name) => match handle.get_property::<T>(name) {
+                    Ok(value) => Some(value),
+                    Err(Error::PropertyUnavailable | Error::PropertyNotFound) => None,
+                    Err(e) => return Err(e),
+                },
+            };
+            handler(handle, value)
+        }))))
+    }
+
+    /// Call `handler` for every event with the given [`EventId`], enabling it with [`Handle::request_event()`].
+    ///
+    /// This runs after any more specific handler for the same event.
+    pub fn on_event(&mut self, event_id: EventId, handler: impl FnMut(&Handle, &Event) -> Result<()> + 'a) -> Result<HandlerId> {
+        self.handle.request_event(event_id, true)?;
+        Ok(self.register(next_userdata(), Handler::Event(event_id, Box::new(handler))))
+    }
+
+    /// Register a hook with the given `name` and `priority` (see [`Handle::hook_add()`]), and call `handler` whenever it is invoked.
+    ///
+    /// The hook is continued with [`Handle::hook_continue()`] once `handler` returns, even if it fails.
+    ///
+    /// mpv can't unregister hooks. Once removed with [`Dispatcher::remove()`], the hook is continued right away instead.
+    pub fn on_hook(&mut self, name: &str, priority: i32, handler: impl FnMut(&Handle, &Hook) -> Result<()> + 'a) -> Result<HandlerId> {
+        let id = next_userdata();
+        self.handle.hook_add(id, name, priority)?;
+        Ok(self.register(id, Handler::Hook(Box::new(handler))))
+    }
+
+    /// Register an [`ON_LOAD`](crate::hooks::ON_LOAD) hook with the given `priority`, and call `handler` with an [`OnLoad`] context whenever it is invoked.
+    ///
+    /// As with [`Dispatcher::on_hook()`], the hook is continued once `handler` returns, even if it fails.
+    pub fn on_load(&mut self, priority: i32, mut handler: impl FnMut(&OnLoad) -> Result<()> + 'a) -> Result<HandlerId> {
+        self.on_hook(hooks::ON_LOAD, priority, move |handle, hook| handler(&OnLoad::borrowed(handle, hook)))
+    }
+
+    /// Call `handler` for every [`Event::ClientMessage`] whose first argument is `name` (such as sent by `script-message-to <client> <name> ...`),
+    /// with the remaining arguments.
+    pub fn on_client_message(&mut self, name: &str, handler: impl FnMut(&Handle, &[String]) -> Result<()> + 'a) -> HandlerId {
+        self.register(next_userdata(), Handler::ClientMessage(name.to_string(), Box::new(handler)))
+    }
+
+    /// Call `handler` once mpv is shutting down, just before [`Dispatcher::run()`] returns.
+    pub fn on_shutdown(&mut self, handler: impl FnMut(&Handle) -> Result<()> + 'a) -> HandlerId {
+        self.register(next_userdata(), Handler::Shutdown(Box::new(handler)))
+    }
+
+    /// Receive log messages up to `max_level` (see [`Handle::request_log_messages()`]), and pass them to `forward`,
+    /// such as [`logging::to_log()`](crate::logging::to_log()) or [`logging::to_tracing()`](crate::logging::to_tracing()).
+    ///
+    /// Removing the handler doesn't stop mpv from sending log messages; use [`Handle::request_log_messages()`] with [`LogLevel::None`] for that.
+    pub fn forward_log_messages(&mut self, max_level: LogLevel, mut forward: impl FnMut(&LogMessage) + 'a) -> Result<HandlerId> {
+        self.handle.request_log_messages(max_level)?;
+        self.on_event(EventId::LOG_MESSAGE, move |_handle, event| {
+            if let Event::LogMessage(message) = event {
+                forward(message);
+            }
+            Ok(())
+        })
+    }
+
+    /// Remove a handler, unobserving its property if it has one. Returns `false` if it was already removed.
+    pub fn remove(&mut self, id: HandlerId) -> Result<bool> {
+        match self.handlers.get(&id.0) {
+            None | Some(Handler::RemovedHook) => Ok(false),
+            Some(Handler::Property(..)) => {
+                self.handle.unobserve_property(id.0)?;
+                self.handlers.remove(&id.0);
+                Ok(true)
+            }
+            Some(Handler::Hook(_)) => {
+                self.handlers.insert(id.0, Handler::RemovedHook);
+                Ok(true)
+            }
+            Some(_) => {
+                self.handlers.remove(&id.0);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Dispatch the event at `ptr`, as returned by `Handle::wait_event_raw()`.
+    pub(crate) fn dispatch(&mut self, ptr: *mut mpv_event) -> Result<()> {
+        let raw = unsafe { *ptr };
+        let id = EventId(raw.event_id);
+
+        // Property changes are decoded by their handler, directly into the type it observes.
+        let mut handled = false;
+        if raw.event_id == mpv_event_id_MPV_EVENT_PROPERTY_CHANGE && !raw.data.is_null()
+            && let Some(Handler::Property(_, handler)) = self.handlers.get_mut(&raw.reply_userdata) {
+            handler(&self.handle, PropertySource::Event(unsafe { &*(raw.data as *const mpv_event_property) }))?;
+            handled = true;
+        }
+
+        // Only decode the event if something below needs it.
+        let wanted = matches!(id, EventId::HOOK | EventId::CLIENT_MESSAGE | EventId::SHUTDOWN | EventId::QUEUE_OVERFLOW)
+            || self.handlers.values().any(|h| matches!(h, Handler::Event(event_id, _) if *event_id == id));
+        if !wanted {
+            return Ok(());
+        }
+
+        let event = match Event::from_ptr(ptr) {
+            Ok(event) => event,
+            // The property handler already got this change, so a value the generic decoding can't handle isn't fatal.
+            Err(_) if handled => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        match &event {
+            Event::Hook(hook) => {
+                let result = match self.handlers.get_mut(&hook.userdata) {
+                    Some(Handler::Hook(handler)) => traced!("mpv_hook", [hook = hook.name.as_str(), id = hook.id], handler(&self.handle, hook)),
+                    _ => Ok(()),
+                };
+                self.handle.hook_continue(hook.id)?;
+                result?;
+            }
+            Event::ClientMessage(message) => {
+                if let Some((name, args)) = message.0.split_first() {
+                    for handler in self.handlers.values_mut() {
+                        if let Handler::ClientMessage(n, handler) = handler && n == name {
+                            handler(&self.handle, args)?;
+                        }
+                    }
+                }
+            }
+            Event::QueueOverflow => {
+                self.overflow_count += 1;
+                if self.resync_on_overflow {
+                    for handler in self.handlers.values_mut() {
+                        if let Handler::Property(name, handler) = handler {
+                            handler(&self.handle, PropertySource::Fetch(name))?;
+                        }
+                    }
+                }
+            }
+            Event::Shutdown => {
+                self.shutdown = true;
+                for handler in self.handlers.values_mut() {
+                    if let Handler::Shutdown(handler) = handler {
+                        handler(&self.handle)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        for handler in self.handlers.values_mut() {
+            if let Handler::Event(event_id, handler) = handler && *event_id == id {
+                handler(&self.handle, &event)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Wait up to `timeout` seconds for one event and dispatch it.
+    ///
+    /// `timeout` has the same meaning as in [`Handle::wait_event()`].
+    /// Returns `false` once mpv is shutting down, after which the [`Dispatcher`] should be dropped.
+    pub fn poll(&mut self, timeout: f64) -> Result<bool> {
+        if self.shutdown {
+            return Ok(false);
+        }
+
+        let ptr = self.handle.wait_event_raw(timeout);
+        check_null!(ptr);
+
+        // Traced separately from the wait, to tell slow handlers apart from time spent blocked in mpv.
+        traced!("mpv_dispatch", [event_id = unsafe { (*ptr).event_id }], self.dispatch(ptr))?;
+
+        Ok(!self.shutdown)
+    }
+
+    /// Dispatch events until mpv shuts down.
+    pub fn run(&mut self) -> Result<()> {
+        while self.poll(-1.0)? {}
+        Ok(())
+    }
+}
@@ -30,7 +30,7 @@ pub(crate) fn error_to_result(value: c_int) -> Result<()> {
 }
 
 /// A structure representing a mismatch between an expected and actual version number.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VersionError {
     /// The version number that was expected.
     pub expected: u64,
@@ -38,8 +38,47 @@ pub struct VersionError {
     pub found: u64,
 }
 
+/// A structure describing why and where a JSON document could not be written or parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    /// A description of the problem.
+    pub message: &'static str,
+    /// The byte offset into the input (or output, while writing) at which the problem was found.
+    pub position: usize,
+}
+
+/// A structure describing why a [`Node`](crate::Node) could not be converted into a Rust type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeError {
+    /// The location of the offending value within the converted [`Node`](crate::Node),
+    /// as `/`-separated map keys and array indices (e.g. `/track-list/0/id`). Empty if it is the converted [`Node`](crate::Node) itself.
+    pub path: String,
+    /// A description of the expected value.
+    pub expected: &'static str,
+    /// A description of the value that was found instead.
+    pub found: String,
+}
+
+impl NodeError {
+    /// Prepend `segment` (a map key or array index) to [`NodeError::path`], when propagating the error out of a nested value.
+    pub(crate) fn at(mut self, segment: &str) -> Self {
+        self.path.insert_str(0, segment);
+        self.path.insert(0, '/');
+        self
+    }
+}
+
+/// A structure describing a number which does not fit into the type it was converted into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeError {
+    /// The number which was out of range.
+    pub value: String,
+    /// The type it was converted into, such as `u32` or `i64`.
+    pub target: &'static str,
+}
+
 /// A debug struct for marking specific locations in code.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DebugLoc {
     /// The referenced file.
     pub file: &'static str,
@@ -52,7 +91,8 @@ pub struct DebugLoc {
 }
 
 /// Enum containing the possible errors in the Rust wrapper over mpv, which may be caused by data passed from mpv or by the user.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum RustError {
     /// Invalid UTF-8 data was encountered while parsing a C string into a Rust string.
     InvalidUtf8(Utf8Error),
@@ -64,10 +104,36 @@ pub enum RustError {
     ///
     /// This can happen occasionally, especially during init, and is not fatal.
     Pointer(Option<DebugLoc>),
+    /// A JSON document could not be written or parsed.
+    Json(JsonError),
+    /// An operation did not complete within its timeout.
+    Timeout,
+    /// An operation was interrupted because mpv is shutting down (see [`Event::Shutdown`](crate::Event::Shutdown)).
+    Shutdown,
+    /// A remote procedure call failed on the remote side, with the given message.
+    ///
+    /// See [`Rpc::call()`](crate::rpc::Rpc::call()).
+    Rpc(String),
+    /// A value could not be serialized into or deserialized from a [`Node`](crate::Node), with the given message.
+    ///
+    /// See [`to_node()`](crate::to_node()) and [`from_node()`](crate::from_node()), which require the `serde` feature.
+    Serde(String),
+    /// A [`Node`](crate::Node) did not have the shape required by the type it was converted into.
+    ///
+    /// See [`FromNode`](crate::FromNode).
+    NodeConversion(NodeError),
+    /// A number received from or sent to mpv does not fit into the type it was converted into,
+    /// such as a negative [`Format::INT64`](crate::Format::INT64) received as a [`u32`].
+    ///
+    /// Within a [`FromNode`](crate::FromNode) conversion, this is reported as [`RustError::NodeConversion`] instead, which includes the location.
+    OutOfRange(RangeError),
+    /// A [`Node::Unknown`](crate::Node::Unknown), which holds a value of a format this crate doesn't know about, was sent to mpv.
+    UnknownNodeFormat(libmpv_client_sys::mpv_format),
 }
 
 /// List of error codes than can be returned by API functions.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Error {
     /// No error happened (used to signal successful operation).
     ///
@@ -115,6 +181,8 @@ pub enum Error {
     Generic,
     /// Rust implementation specific error.
     Rust(RustError),
+    /// An error code this crate doesn't know about yet, returned by a newer version of mpv, with its raw value.
+    Unknown(c_int),
 }
 
 impl From<Utf8Error> for Error {
@@ -153,7 +221,7 @@ impl From<c_int> for Error {
             libmpv_client_sys::mpv_error_MPV_ERROR_UNSUPPORTED => Error::Unsupported,
             libmpv_client_sys::mpv_error_MPV_ERROR_NOT_IMPLEMENTED => Error::NotImplemented,
             libmpv_client_sys::mpv_error_MPV_ERROR_GENERIC => Error::Generic,
-            _ => unimplemented!(),
+            code => Error::Unknown(code),
         }
     }
 }
@@ -183,6 +251,7 @@ impl From<&Error> for c_int {
             Error::Generic => libmpv_client_sys::mpv_error_MPV_ERROR_GENERIC,
             Error::Rust(_) => libmpv_client_sys::mpv_error_MPV_ERROR_GENERIC,
             Error::Success(x) => *x as c_int,
+            Error::Unknown(code) => *code,
         }
     }
 }
\ No newline at end of file
@@ -1,12 +1,13 @@
 //! The various [`Event`]s and their payloads which may be sent by mpv.
 
 use std::ffi::{c_void, CStr};
-use libmpv_client_sys::{mpv_event, mpv_event_client_message, mpv_event_command, mpv_event_end_file, mpv_event_hook, mpv_event_id, mpv_event_id_MPV_EVENT_AUDIO_RECONFIG, mpv_event_id_MPV_EVENT_CLIENT_MESSAGE, mpv_event_id_MPV_EVENT_COMMAND_REPLY, mpv_event_id_MPV_EVENT_END_FILE, mpv_event_id_MPV_EVENT_FILE_LOADED, mpv_event_id_MPV_EVENT_GET_PROPERTY_REPLY, mpv_event_id_MPV_EVENT_HOOK, mpv_event_id_MPV_EVENT_IDLE, mpv_event_id_MPV_EVENT_LOG_MESSAGE, mpv_event_id_MPV_EVENT_PLAYBACK_RESTART, mpv_event_id_MPV_EVENT_PROPERTY_CHANGE, mpv_event_id_MPV_EVENT_QUEUE_OVERFLOW, mpv_event_id_MPV_EVENT_SEEK, mpv_event_id_MPV_EVENT_SET_PROPERTY_REPLY, mpv_event_id_MPV_EVENT_SHUTDOWN, mpv_event_id_MPV_EVENT_START_FILE, mpv_event_id_MPV_EVENT_TICK, mpv_event_id_MPV_EVENT_VIDEO_RECONFIG, mpv_event_log_message, mpv_event_property, mpv_event_start_file, mpv_format};
+use libmpv_client_sys::{mpv_event, mpv_event_client_message, mpv_event_command, mpv_event_end_file, mpv_end_file_reason, mpv_event_hook, mpv_event_id, mpv_event_id_MPV_EVENT_AUDIO_RECONFIG, mpv_event_id_MPV_EVENT_CLIENT_MESSAGE, mpv_event_id_MPV_EVENT_COMMAND_REPLY, mpv_event_id_MPV_EVENT_END_FILE, mpv_event_id_MPV_EVENT_FILE_LOADED, mpv_event_id_MPV_EVENT_GET_PROPERTY_REPLY, mpv_event_id_MPV_EVENT_HOOK, mpv_event_id_MPV_EVENT_IDLE, mpv_event_id_MPV_EVENT_LOG_MESSAGE, mpv_event_id_MPV_EVENT_PLAYBACK_RESTART, mpv_event_id_MPV_EVENT_PROPERTY_CHANGE, mpv_event_id_MPV_EVENT_QUEUE_OVERFLOW, mpv_event_id_MPV_EVENT_SEEK, mpv_event_id_MPV_EVENT_SET_PROPERTY_REPLY, mpv_event_id_MPV_EVENT_SHUTDOWN, mpv_event_id_MPV_EVENT_START_FILE, mpv_event_id_MPV_EVENT_TICK, mpv_event_id_MPV_EVENT_VIDEO_RECONFIG, mpv_event_log_message, mpv_event_property, mpv_event_start_file, mpv_format};
 use crate::*;
 use crate::error::error_to_result_code;
 use crate::types::traits::MpvRecvInternal;
 
 /// [`Event`] IDs for use with [`Handle::request_event()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct EventId(pub(crate) mpv_event_id);
 
 impl EventId {
@@ -50,6 +51,7 @@ impl EventId {
 
 /// The possible log levels that mpv can apply to log messages.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum LogLevel {
     /// disable logging
     None,
@@ -94,7 +96,8 @@ impl LogLevel {
 }
 
 /// Possible reasons for an [`Event::EndFile`].
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum EndFileReason {
     /// The end of file was reached.
     ///
@@ -114,12 +117,15 @@ pub enum EndFileReason {
     /// When the playlist is read, its entries will be appended to the playlist after the entry of the current file, the entry of the current file is removed, and an [`Event::EndFile`] is sent with [`EndFile.reason`](field@EndFile::reason) set to [`EndFileReason::Redirect`].
     /// Then playback continues with the playlist contents.
     Redirect,
+    /// A reason this crate doesn't know about yet, sent by a newer version of mpv, with its raw value.
+    Unknown(mpv_end_file_reason),
 }
 
 /// Events that may be received from [`Handle::wait_event()`].
 ///
 /// Some are just informational, while some contain additional data and some are responses to mpv commands.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Event {
     /// Nothing happened. Happens on timeouts or sporadic wakeups.
     None,
@@ -187,6 +193,10 @@ pub enum Event {
     ///
     /// If you receive this, you **must** handle it and continue the hook with [`Handle::hook_continue()`].
     Hook(Hook),
+    /// An event this crate doesn't know about yet, sent by a newer version of mpv, with its raw event ID.
+    ///
+    /// Any data attached to it is ignored.
+    Unknown(mpv_event_id),
 }
 
 /// Details provided to [`Event::LogMessage`].
@@ -229,8 +239,7 @@ pub struct SetPropertyReply {
 pub struct CommandReply {
     /// Result of a command (which may be `Node::None` even on success depending on the command), or an error if one occurred.
     pub result: Result<Node>,
-    /// `userdata` value passed to the mpv request which generated this event, if provided.
-    // TODO: Update doc with link to async function when implemented.
+    /// `userdata` value passed to [`Handle::command_node_async()`](crate::Handle::command_node_async()).
     pub userdata: u64,
 }
 
@@ -300,6 +309,35 @@ pub struct Hook {
 }
 
 impl Event {
+    /// The [`EventId`] of this event.
+    ///
+    /// [`Event::None`] has an ID of its own, which can't be requested with [`Handle::request_event()`].
+    pub fn id(&self) -> EventId {
+        #[allow(deprecated)]
+        match self {
+            Event::None => EventId(libmpv_client_sys::mpv_event_id_MPV_EVENT_NONE),
+            Event::Shutdown => EventId::SHUTDOWN,
+            Event::LogMessage(_) => EventId::LOG_MESSAGE,
+            Event::GetPropertyReply(_) => EventId::GET_PROPERTY_REPLY,
+            Event::SetPropertyReply(_) => EventId::SET_PROPERTY_REPLY,
+            Event::CommandReply(_) => EventId::COMMAND_REPLY,
+            Event::StartFile(_) => EventId::START_FILE,
+            Event::EndFile(_) => EventId::END_FILE,
+            Event::FileLoaded => EventId::FILE_LOADED,
+            Event::Idle => EventId::IDLE,
+            Event::Tick => EventId::TICK,
+            Event::ClientMessage(_) => EventId::CLIENT_MESSAGE,
+            Event::VideoReconfig => EventId::VIDEO_RECONFIG,
+            Event::AudioReconfig => EventId::AUDIO_RECONFIG,
+            Event::Seek => EventId::SEEK,
+            Event::PlaybackRestart => EventId::PLAYBACK_RESTART,
+            Event::PropertyChange(_) => EventId::PROPERTY_CHANGE,
+            Event::QueueOverflow => EventId::QUEUE_OVERFLOW,
+            Event::Hook(_) => EventId::HOOK,
+            Event::Unknown(id) => EventId(*id),
+        }
+    }
+
     pub(crate) fn from_ptr(ptr: *const mpv_event) -> Result<Event> {
         check_null!(ptr);
         let event = unsafe { *ptr };
@@ -326,7 +364,7 @@ impl Event {
             libmpv_client_sys::mpv_event_id_MPV_EVENT_PROPERTY_CHANGE => Ok(Event::PropertyChange(PropertyChange::from_event(event)?)),
             libmpv_client_sys::mpv_event_id_MPV_EVENT_QUEUE_OVERFLOW => Ok(Event::QueueOverflow),
             libmpv_client_sys::mpv_event_id_MPV_EVENT_HOOK => Ok(Event::Hook(Hook::from_event(event)?)),
-            _ => unimplemented!(),
+            id => Ok(Event::Unknown(id)),
         }
     }
 }
@@ -336,15 +374,15 @@ impl LogMessage {
         check_null!(event.data);
         let event_log_message = unsafe { *(event.data as *const mpv_event_log_message) };
 
+        // Unknown levels from a newer mpv are rounded to the next noisier known level.
         let level = match event_log_message.log_level {
-            libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_FATAL => LogLevel::Fatal,
-            libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_ERROR => LogLevel::Error,
-            libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_WARN => LogLevel::Warn,
-            libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_INFO => LogLevel::Info,
-            libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_V => LogLevel::Verbose,
-            libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_DEBUG => LogLevel::Debug,
-            libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_TRACE => LogLevel::Trace,
-            _ => unimplemented!()
+            l if l <= libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_FATAL => LogLevel::Fatal,
+            l if l <= libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_ERROR => LogLevel::Error,
+            l if l <= libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_WARN => LogLevel::Warn,
+            l if l <= libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_INFO => LogLevel::Info,
+            l if l <= libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_V => LogLevel::Verbose,
+            l if l <= libmpv_client_sys::mpv_log_level_MPV_LOG_LEVEL_DEBUG => LogLevel::Debug,
+            _ => LogLevel::Trace,
         };
 
         check_null!(event_log_message.prefix);
@@ -441,7 +479,7 @@ impl EndFile {
             libmpv_client_sys::mpv_end_file_reason_MPV_END_FILE_REASON_QUIT => EndFileReason::Quit,
             libmpv_client_sys::mpv_end_file_reason_MPV_END_FILE_REASON_ERROR => EndFileReason::Error(Error::from(event_end_file.error)),
             libmpv_client_sys::mpv_end_file_reason_MPV_END_FILE_REASON_REDIRECT => EndFileReason::Redirect,
-            _ => unimplemented!(),
+            reason => EndFileReason::Unknown(reason),
         };
 
         Ok(Self {
@@ -487,6 +525,7 @@ impl Hook {
 
 #[derive(Debug)]
 /// An enum of the possible values returned in a [`GetPropertyReply`] or a [`PropertyChange`].
+#[non_exhaustive]
 pub enum PropertyValue {
     /// Sometimes used for empty values or errors. See [`Format::NONE`].
     None,
@@ -508,6 +547,8 @@ pub enum PropertyValue {
     NodeMap(NodeMap),
     /// A [`ByteArray`] property. See [`Format::BYTE_ARRAY`].
     ByteArray(ByteArray),
+    /// A format this crate doesn't know about yet, sent by a newer version of mpv, with its raw value. The data is ignored.
+    Unknown(mpv_format),
 }
 
 impl PropertyValue {
@@ -523,7 +564,7 @@ impl PropertyValue {
             libmpv_client_sys::mpv_format_MPV_FORMAT_NODE_ARRAY => Ok(Self::NodeArray(unsafe { NodeArray::from_ptr(data)? })),
             libmpv_client_sys::mpv_format_MPV_FORMAT_NODE_MAP => Ok(Self::NodeMap(unsafe { NodeMap::from_ptr(data)? })),
             libmpv_client_sys::mpv_format_MPV_FORMAT_BYTE_ARRAY => Ok(Self::ByteArray(unsafe { ByteArray::from_ptr(data)? })),
-            _ => unimplemented!()
+            format => Ok(Self::Unknown(format)),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,15 +1,22 @@
 //! Definition and implementation of [`Handle`], this crate's primary interface to mpv.
 
-use std::ffi::{CStr, CString};
+use std::ffi::{CStr, CString, OsStr, c_void};
 use std::mem::MaybeUninit;
 use std::ops::Deref;
-use std::ptr::null;
+use std::ptr::{null, null_mut};
 
+#[cfg(not(test))]
 use libmpv_client_sys as mpv;
+#[cfg(test)]
+use crate::tests::mpv;
 use libmpv_client_sys::mpv_node;
 use crate::*;
 use crate::error::{error_to_result, error_to_result_code};
 use crate::event::LogLevel;
+#[cfg(feature = "instrument")]
+use crate::instrument;
+use crate::observer::Observer;
+use crate::types::os_string::os_str_to_cstring;
 use crate::types::traits::{MpvRecv, MpvSend, MpvSendInternal};
 
 /// The primary interface to mpv.
@@ -213,16 +220,38 @@ impl Handle {
     ///# }
     /// ```
     pub fn command(&self, command: &[impl AsRef<str>]) -> Result<()> {
-        let mut owned_strings = Vec::with_capacity(command.len());
-        for s in command {
-            owned_strings.push(CString::new(s.as_ref())?);
-        }
-
-        let mut cstrs: Vec<_> = owned_strings.iter().map(|s| s.as_ptr()).collect();
-        cstrs.push(null());
+        self.command_os(&command.iter().map(|s| OsStr::new(s.as_ref())).collect::<Vec<_>>())
+    }
 
-        let err = unsafe { mpv::command(self.handle, cstrs.as_mut_ptr()) };
-        error_to_result(err)
+    /// Same as [`Handle::command()`], but takes any [`OsStr`]-like type, such as [`&Path`](std::path::Path),
+    /// so filenames which are not valid UTF-8 can be passed on Unix.
+    ///
+    /// # Example
+    /// ```
+    ///# use libmpv_client::*;
+    ///# use std::ffi::OsStr;
+    ///#
+    ///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+    ///#     let handle = Handle::from_ptr(ptr);
+    /// // Mixing strings and paths requires converting them to the same type.
+    /// let path = std::path::Path::new("/media/video.mkv");
+    /// handle.command_os(&[OsStr::new("loadfile"), path.as_os_str(), OsStr::new("append")])?;
+    ///#     Ok(())
+    ///# }
+    /// ```
+    pub fn command_os(&self, command: &[impl AsRef<OsStr>]) -> Result<()> {
+        traced!("mpv_command", [command = ?command.first().map(|c| c.as_ref()), args = command.len()], {
+            let mut owned_strings = Vec::with_capacity(command.len());
+            for s in command {
+                owned_strings.push(os_str_to_cstring(s.as_ref())?);
+            }
+
+            let mut cstrs: Vec<_> = owned_strings.iter().map(|s| s.as_ptr()).collect();
+            cstrs.push(null());
+
+            let err = unsafe { mpv::command(self.handle, cstrs.as_mut_ptr()) };
+            error_to_result(err)
+        })
     }
 
     /// Same as [`Handle::command_ret()`], but allows passing structured data in any format.
@@ -265,15 +294,17 @@ impl Handle {
     ///# }
     /// ```
     pub fn command_node(&self, command: Node) -> Result<Node> {
-        let mut return_mpv_node = MaybeUninit::uninit();
-        
-        command.to_mpv(|x| {
-            let err = unsafe { mpv::command_node(self.handle, x as *mut mpv_node, return_mpv_node.as_ptr() as *mut mpv_node) };
-            error_to_result_code(err)
-        }).and_then(|_| {
-            let ret = unsafe { Node::from_node_ptr(return_mpv_node.as_ptr()) };
-            unsafe { mpv_free_node_contents(return_mpv_node.as_mut_ptr()) }
-            ret
+        traced!("mpv_command_node", [command = instrument::command_name(&command), formats = ?instrument::node_formats(&command)], {
+            let mut return_mpv_node = MaybeUninit::uninit();
+
+            command.to_mpv(|x| {
+                let err = unsafe { mpv::command_node(self.handle, x as *mut mpv_node, return_mpv_node.as_ptr() as *mut mpv_node) };
+                error_to_result_code(err)
+            }).and_then(|_| {
+                let ret = unsafe { Node::from_node_ptr(return_mpv_node.as_ptr()) };
+                unsafe { mpv_free_node_contents(return_mpv_node.as_mut_ptr()) }
+                ret
+            })
         })
     }
 
@@ -287,34 +318,87 @@ impl Handle {
     /// # Return
     /// If the function succeeds, [`Result<Node>`] is command-specific return data. Few commands actually use this.
     pub fn command_ret(&self, command: &[impl AsRef<str>]) -> Result<Node> {
-        let mut owned_strings = Vec::with_capacity(command.len());
-        for s in command {
-            owned_strings.push(CString::new(s.as_ref())?);
-        }
+        self.command_ret_os(&command.iter().map(|s| OsStr::new(s.as_ref())).collect::<Vec<_>>())
+    }
+
+    /// Same as [`Handle::command_ret()`], but takes any [`OsStr`]-like type, as [`Handle::command_os()`] does.
+    pub fn command_ret_os(&self, command: &[impl AsRef<OsStr>]) -> Result<Node> {
+        traced!("mpv_command_ret", [command = ?command.first().map(|c| c.as_ref()), args = command.len()], {
+            let mut owned_strings = Vec::with_capacity(command.len());
+            for s in command {
+                owned_strings.push(os_str_to_cstring(s.as_ref())?);
+            }
+
+            let mut cstrs: Vec<_> = owned_strings.iter().map(|s| s.as_ptr()).collect();
+            cstrs.push(null());
 
-        let mut cstrs: Vec<_> = owned_strings.iter().map(|s| s.as_ptr()).collect();
-        cstrs.push(null());
-        
-        let mut return_mpv_node = MaybeUninit::uninit();
+            let mut return_mpv_node = MaybeUninit::uninit();
 
-        let err = unsafe { mpv::command_ret(self.handle, cstrs.as_mut_ptr(), return_mpv_node.as_mut_ptr()) };
-        error_to_result_code(err).and_then(|_| {
-            let ret = unsafe { Node::from_node_ptr(return_mpv_node.as_ptr()) };
-            unsafe { mpv_free_node_contents(return_mpv_node.as_mut_ptr()) }
-            ret
+            let err = unsafe { mpv::command_ret(self.handle, cstrs.as_mut_ptr(), return_mpv_node.as_mut_ptr()) };
+            error_to_result_code(err).and_then(|_| {
+                let ret = unsafe { Node::from_node_ptr(return_mpv_node.as_ptr()) };
+                unsafe { mpv_free_node_contents(return_mpv_node.as_mut_ptr()) }
+                ret
+            })
         })
     }
 
+    /// Same as [`Handle::command_node()`], but run it asynchronously.
+    ///
+    /// This returns as soon as the command has been queued. Once it completes, an [`Event::CommandReply`] is received,
+    /// with its [`userdata`](field@event::CommandReply::userdata) set to `userdata` and its result set to the command's return data.
+    /// The command can be aborted with [`Handle::abort_async_command()`].
+    ///
+    /// # Params
+    /// - `userdata`: Identifies the request in [`Event::CommandReply`] and [`Handle::abort_async_command()`].
+    /// - `command`: As in [`Handle::command_node()`].
+    ///
+    /// # Example
+    /// ```
+    ///# use libmpv_client::*;
+    ///#
+    ///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+    ///#     let handle = Handle::from_ptr(ptr);
+    /// handle.command_node_async(42, node_array!("loadfile", "https://example.com/video.mkv"))?;
+    ///
+    /// loop {
+    ///     if let Event::CommandReply(reply) = handle.wait_event(-1.0)? && reply.userdata == 42 {
+    ///         reply.result?;
+    ///         break;
+    ///     }
+    /// }
+    ///#     Ok(())
+    ///# }
+    /// ```
+    pub fn command_node_async(&self, userdata: u64, command: Node) -> Result<()> {
+        command.to_mpv(|x| {
+            let err = unsafe { mpv::command_node_async(self.handle, userdata, x as *mut mpv_node) };
+            error_to_result_code(err)
+        }).map(|_| ())
+    }
+
+    /// Signal all asynchronous commands started with the given `userdata` to abort.
+    ///
+    /// This does not wait for the commands to stop. They still finish with an [`Event::CommandReply`] as usual, but with some work not done.
+    /// How this is signaled depends on the command (for example, the `subprocess` command sets `killed_by_us` in its result).
+    ///
+    /// Commands which don't support aborting, as well as commands which have already completed, are not affected.
+    pub fn abort_async_command(&self, userdata: u64) {
+        unsafe { mpv::abort_async_command(self.handle, userdata) }
+    }
+
     /// Same as [`Handle::command()`], but use input.conf parsing for splitting arguments.
     ///
     /// This is slightly simpler, but also more error-prone, since arguments may need quoting/escaping.
     ///
     /// This also has OSD and string expansion enabled by default.
     pub fn command_string(&self, command: &str) -> Result<()> {
-        let owned_string = CString::new(command)?;
+        traced!("mpv_command_string", [command], {
+            let owned_string = CString::new(command)?;
 
-        let err = unsafe { mpv::command_string(self.handle, owned_string.as_ptr()) };
-        error_to_result(err)
+            let err = unsafe { mpv::command_string(self.handle, owned_string.as_ptr()) };
+            error_to_result(err)
+        })
     }
 
     /// Set a property to a given value.
@@ -322,7 +406,7 @@ impl Handle {
     /// Properties are essentially variables that can be queried or set at runtime. For example, writing to the pause property will actually pause or unpause playback.
     ///
     /// # Params
-    /// If the [`MpvFormat::MPV_FORMAT`] of `value` doesn't match with the internal [`mpv_format`](libmpv_client_sys::mpv_format) format of the property,
+    /// If the [`Format`] of `value` doesn't match with the internal [`mpv_format`](libmpv_client_sys::mpv_format) format of the property,
     /// access usually will fail with [`Error::PropertyFormat`].
     ///
     /// In some cases, the data is automatically converted and access succeeds. For example, mpv converts [`i64`] to [`f64`],
@@ -343,12 +427,14 @@ impl Handle {
     ///# }
     /// ```
     pub fn set_property<T: MpvSend>(&self, name: &str, value: T) -> Result<()> {
-        let owned_name = CString::new(name)?;
+        traced!("mpv_set_property", [property = name, format = instrument::format_name(T::MPV_FORMAT.0)], {
+            let owned_name = CString::new(name)?;
 
-        value.to_mpv(|x| {
-            let err = unsafe { mpv::set_property(self.handle, owned_name.as_ptr(), T::MPV_FORMAT.0, x) };
-            error_to_result_code(err)
-        }).map(|_| ())
+            value.to_mpv(|x| {
+                let err = unsafe { mpv::set_property(self.handle, owned_name.as_ptr(), T::MPV_FORMAT.0, x) };
+                error_to_result_code(err)
+            }).map(|_| ())
+        })
     }
 
     /// Convenience function to delete a property.
@@ -363,7 +449,7 @@ impl Handle {
 
     /// Read the value of the given property.
     ///
-    /// If the [`MpvFormat::MPV_FORMAT`] of the requested type doesn't match with the internal [`mpv_format`](libmpv_client_sys::mpv_format) format of the property,
+    /// If the [`Format`] of the requested type doesn't match with the internal [`mpv_format`](libmpv_client_sys::mpv_format) format of the property,
     /// access usually will fail with [`Error::PropertyFormat`].
     ///
     /// In some cases, the data is automatically converted and access succeeds. For example, [`i64`] is always converted to [`f64`],
@@ -383,14 +469,45 @@ impl Handle {
     ///# }
     /// ```
     pub fn get_property<T: MpvRecv>(&self, name: &str) -> Result<T> {
-        let owned_name = CString::new(name)?;
+        traced!("mpv_get_property", [property = name, format = instrument::format_name(T::MPV_FORMAT.0)], {
+            let owned_name = CString::new(name)?;
+
+            unsafe {
+                T::from_mpv(|x| {
+                    let err = mpv::get_property(self.handle, owned_name.as_ptr(), T::MPV_FORMAT.0, x);
+                    error_to_result_code(err)
+                })
+            }
+        })
+    }
 
-        unsafe {
-            T::from_mpv(|x| {
-                let err = mpv::get_property(self.handle, owned_name.as_ptr(), T::MPV_FORMAT.0, x);
-                error_to_result_code(err)
-            })
-        }
+    /// Read the value of the given property as a [`NodeGuard`], without copying it.
+    ///
+    /// The returned data stays owned by mpv and is read in place through [`NodeRef`]s, so this is much cheaper
+    /// than receiving a [`Node`] when only a few values of a large property are needed. It is freed when the guard is dropped.
+    ///
+    /// # Example
+    /// ```
+    ///# use libmpv_client::*;
+    ///#
+    ///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+    ///#     let handle = Handle::from_ptr(ptr);
+    /// let playlist = handle.get_property_ref("playlist")?;
+    /// let count = playlist.get().len();
+    /// let first = playlist.get().at(0).and_then(|entry| entry.get("filename")).and_then(|f| f.as_str());
+    ///#     Ok(())
+    ///# }
+    /// ```
+    pub fn get_property_ref(&self, name: &str) -> Result<NodeGuard> {
+        traced!("mpv_get_property", [property = name, format = "node"], {
+            let owned_name = CString::new(name)?;
+            let mut node: MaybeUninit<mpv_node> = MaybeUninit::uninit();
+
+            let err = unsafe { mpv::get_property(self.handle, owned_name.as_ptr(), Format::NODE.0, node.as_mut_ptr() as *mut c_void) };
+            error_to_result(err)?;
+
+            Ok(unsafe { NodeGuard::from_raw(node.assume_init()) })
+        })
     }
 
     /// Get a notification whenever the given property changes.
@@ -423,6 +540,7 @@ impl Handle {
     ///
     /// # Params
     /// - `userdata`: This will be used for the [`PropertyChange.userdata`](field@event::PropertyChange::userdata) field for the received [`Event::PropertyChange`] events.
+    ///   Values with the highest bit set are reserved for [`Handle::observe()`] and [`Dispatcher`](crate::dispatch::Dispatcher).
     // (Also see the section about asynchronous calls, although this function is somewhat different from actual asynchronous calls.)
     ///
     ///   If you have no use for this, pass 0.
@@ -448,6 +566,50 @@ impl Handle {
         error_to_result(err)
     }
 
+    /// Observe the property `name` as a `T`, with a `userdata` value allocated for it.
+    ///
+    /// Use [`Observer::value()`] to pick its changes out of the received [`Event::PropertyChange`] events.
+    /// Only this observer is removed once it is dropped, even if the same property is observed elsewhere.
+    ///
+    /// # Example
+    /// ```
+    ///# use libmpv_client::*;
+    ///#
+    ///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+    ///#     let handle = Handle::from_ptr(ptr);
+    /// let volume = handle.observe::<f64>("volume")?;
+    /// let playlist = handle.observe_changes("playlist")?;
+    ///
+    /// loop {
+    ///     match handle.wait_event(-1.0)? {
+    ///         Event::PropertyChange(change) => {
+    ///             if let Some(value) = volume.value(&change) {
+    ///                 match value? {
+    ///                     Some(volume) => println!("volume is now {volume}"),
+    ///                     None => println!("volume is unavailable"),
+    ///                 }
+    ///             } else if playlist.matches(&change) {
+    ///                 println!("the playlist changed");
+    ///             }
+    ///         }
+    ///         Event::Shutdown => return Ok(()),
+    ///         _ => {}
+    ///     }
+    /// }
+    ///# }
+    /// ```
+    pub fn observe<T: MpvRecv>(&self, name: &str) -> Result<Observer<'_, T>> {
+        Observer::new(self, name, T::MPV_FORMAT)
+    }
+
+    /// Observe the property `name` without fetching its value, with a `userdata` value allocated for it.
+    ///
+    /// This uses [`Format::NONE`], so [`Observer::matches()`] only signals that the property _may_ have changed (see [`Handle::observe_property()`]).
+    /// This is cheaper for large properties which are only read when needed.
+    pub fn observe_changes(&self, name: &str) -> Result<Observer<'_, ()>> {
+        Observer::new(self, name, Format::NONE)
+    }
+
     /// Undo [`Handle::observe_property`].
     ///
     /// This will remove all observed properties for which the given number was passed as `userdata` to [`Handle::observe_property()`].
@@ -570,7 +732,19 @@ impl Handle {
     /// }
     /// ```
     pub fn wait_event(&self, timeout: f64) -> Result<Event> {
-        Event::from_ptr(unsafe { mpv::wait_event(self.handle, timeout) })
+        Event::from_ptr(self.wait_event_raw(timeout))
+    }
+
+    /// Like [`Handle::wait_event()`], but return the raw event, which stays valid until the next call.
+    ///
+    /// With the `instrument` feature, this is the only part traced as `mpv_wait_event`, so the time spent blocked waiting is kept
+    /// separate from the time spent handling the event.
+    pub(crate) fn wait_event_raw(&self, timeout: f64) -> *mut mpv::mpv_event {
+        let wait = || unsafe { mpv::wait_event(self.handle, timeout) };
+        #[cfg(feature = "instrument")]
+        let wait = || instrument::wait_event(timeout, wait);
+
+        wait()
     }
 
     /// Interrupt the current [`Handle::wait_event()`] call.
@@ -584,6 +758,48 @@ impl Handle {
         unsafe { mpv::wakeup(self.handle) }
     }
 
+    /// Set a `callback` which is called when there are new events, so another thread can be woken up to call [`Handle::wait_event()`].
+    ///
+    /// The callback is called from foreign threads, possibly while another API function is running, and must return as soon as possible.
+    /// It must not call any function of the mpv API. A panic inside it is contained, see [`panic`](crate::panic).
+    ///
+    /// Only one callback can be set. Since mpv may still be calling the previous one, it is never freed.
+    ///
+    /// # Example
+    /// ```
+    ///# use libmpv_client::*;
+    ///# use std::sync::mpsc::channel;
+    ///#
+    ///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+    ///#     let handle = Handle::from_ptr(ptr);
+    /// let (sender, receiver) = channel();
+    /// handle.set_wakeup_callback(move || {
+    ///     let _ = sender.send(());
+    /// });
+    ///
+    /// while receiver.recv().is_ok() {
+    ///     // Drain the queue, since one wakeup may stand for several events.
+    ///     loop {
+    ///         match handle.wait_event(0.0)? {
+    ///             Event::None => break,
+    ///             Event::Shutdown => return Ok(()),
+    ///             event => println!("{event:?}"),
+    ///         }
+    ///     }
+    /// }
+    ///#     Ok(())
+    ///# }
+    /// ```
+    pub fn set_wakeup_callback(&self, callback: impl Fn() + Send + Sync + 'static) {
+        let callback: *mut WakeupCallback = Box::into_raw(Box::new(Box::new(callback)));
+        unsafe { mpv::set_wakeup_callback(self.handle, Some(wakeup_trampoline), callback as *mut c_void) }
+    }
+
+    /// Remove the callback set with [`Handle::set_wakeup_callback()`].
+    pub fn clear_wakeup_callback(&self) {
+        unsafe { mpv::set_wakeup_callback(self.handle, None, null_mut()) }
+    }
+
     /// A hook is like a synchronous event that blocks the player. You register a hook handler with this function. You will get an event,
     /// which you need to handle, and once things are ready, you can let the player continue with [`Handle::hook_continue()`].
     ///
@@ -608,10 +824,12 @@ impl Handle {
     ///   But if the name is unknown, the hook event will simply never be raised.
     /// - `priority`: See remarks above. Use 0 as a neutral default.
     pub fn hook_add(&self, userdata: u64, name: &str, priority: i32) -> Result<()> {
-        let owned_name = CString::new(name)?;
+        traced!("mpv_hook_add", [hook = name, priority], {
+            let owned_name = CString::new(name)?;
 
-        let err = unsafe { mpv::hook_add(self.handle, userdata, owned_name.as_ptr(), priority) };
-        error_to_result(err)
+            let err = unsafe { mpv::hook_add(self.handle, userdata, owned_name.as_ptr(), priority) };
+            error_to_result(err)
+        })
     }
 
     /// Respond to an [`Event::Hook`] event. You **must** call this after you have handled the event.
@@ -649,11 +867,20 @@ impl Handle {
     ///# }
     /// ```
     pub fn hook_continue(&self, id: u64) -> Result<()> {
-        let err = unsafe { mpv::hook_continue(self.handle, id) };
-        error_to_result(err)
+        traced!("mpv_hook_continue", [id], {
+            let err = unsafe { mpv::hook_continue(self.handle, id) };
+            error_to_result(err)
+        })
     }
 }
 
+type WakeupCallback = Box<dyn Fn() + Send + Sync>;
+
+unsafe extern "C" fn wakeup_trampoline(d: *mut c_void) {
+    let callback = unsafe { &*(d as *const WakeupCallback) };
+    panic::guard_callback("wakeup callback", callback);
+}
+
 /// An owned client created from a [`Handle`].
 ///
 /// Unlike a [`Handle`], it is safe to call [`mpv_destroy`](libmpv_client_sys::destroy) and [`mpv_terminate_destroy`](libmpv_client_sys::terminate_destroy)
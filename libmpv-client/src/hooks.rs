@@ -0,0 +1,135 @@
+//! Typed contexts for [hooks](https://mpv.io/manual/stable/#hooks), which continue the hook automatically.
+
+use crate::*;
+use crate::event::Hook;
+
+/// The name of the hook run before a file is opened.
+pub const ON_LOAD: &str = "on_load";
+/// The name of the hook run after a file failed to open, which can retry it with another URL.
+pub const ON_LOAD_FAIL: &str = "on_load_fail";
+/// The name of the hook run after a file was opened, but before playback starts, which is where external tracks can be added.
+pub const ON_PRELOADED: &str = "on_preloaded";
+
+/// The context of an [`ON_LOAD`] or [`ON_LOAD_FAIL`] hook, where URL resolvers (such as `ytdl_hook`) replace the URL to open,
+/// redirect it to a playlist or EDL, and set options for the file.
+///
+/// The hook is continued with [`Handle::hook_continue()`] when the context is dropped, so mpv never hangs on it,
+/// even if the resolver returns early with an error.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::hooks::{self, OnLoad};
+///#
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// handle.hook_add(0, hooks::ON_LOAD, 10)?;
+///
+/// loop {
+///     match handle.wait_event(-1.0)? {
+///         Event::Hook(hook) => {
+///             if let Some(on_load) = OnLoad::from_hook(&handle, &hook) {
+///                 let url = on_load.url()?;
+///                 if let Some(id) = url.strip_prefix("myvideo://") {
+///                     on_load.set_url(&format!("https://videos.example/{id}.mp4"))?;
+///                     on_load.set_file_local_option("force-media-title", id)?;
+///                 }
+///             }
+///         }
+///         Event::Shutdown => break,
+///         _ => {}
+///     }
+/// }
+///#     Ok(())
+///# }
+/// ```
+pub struct OnLoad<'h> {
+    handle: &'h Handle,
+    hook_id: u64,
+    continue_on_drop: bool,
+}
+
+impl<'h> OnLoad<'h> {
+    /// Take over `hook` if it is an [`ON_LOAD`] or [`ON_LOAD_FAIL`] hook, which is continued once the returned context is dropped.
+    ///
+    /// `hook` must have been received on `handle`, and must not be continued elsewhere.
+    pub fn from_hook(handle: &'h Handle, hook: &Hook) -> Option<Self> {
+        Self::is_on_load(hook).then_some(Self { handle, hook_id: hook.id, continue_on_drop: true })
+    }
+
+    /// A context for a hook which is continued by the caller, such as [`Dispatcher`](crate::dispatch::Dispatcher).
+    pub(crate) fn borrowed(handle: &'h Handle, hook: &Hook) -> Self {
+        Self { handle, hook_id: hook.id, continue_on_drop: false }
+    }
+
+    pub(crate) fn is_on_load(hook: &Hook) -> bool {
+        hook.name == ON_LOAD || hook.name == ON_LOAD_FAIL
+    }
+
+    /// The [`Handle`] which received the hook.
+    pub fn handle(&self) -> &'h Handle {
+        self.handle
+    }
+
+    /// The URL which is about to be opened (`stream-open-filename`).
+    pub fn url(&self) -> Result<String> {
+        self.handle.get_property("stream-open-filename")
+    }
+
+    /// Open `url` instead of the current URL.
+    ///
+    /// This also accepts `edl://` and `memory://` URLs, see [`OnLoad::set_edl()`] and [`OnLoad::add_playlist()`].
+    pub fn set_url(&self, url: &str) -> Result<()> {
+        self.handle.set_property("stream-open-filename", url)
+    }
+
+    /// Set the option `name` to `value` for the current file only (`file-local-options/<name>`).
+    pub fn set_file_local_option<T: MpvSend>(&self, name: &str, value: T) -> Result<()> {
+        self.handle.set_property(&format!("file-local-options/{name}"), value)
+    }
+
+    /// Replace the current file with a playlist of `urls`, which is inserted into the playlist in its place.
+    ///
+    /// Returns [`Error::InvalidParameter`] if a URL contains a line break.
+    pub fn add_playlist(&self, urls: &[impl AsRef<str>]) -> Result<()> {
+        self.set_url(&playlist_url(urls)?)
+    }
+
+    /// Play the `segments` of an [EDL](https://github.com/mpv-player/mpv/blob/master/DOCS/edl-mpv.rst) as a single file.
+    ///
+    /// Each segment is a file name, optionally followed by its parameters (such as `,start=10,length=20`) as a separate string,
+    /// which is not escaped: `&[("a.mkv", ""), ("b.mkv", ",length=10")]`.
+    pub fn set_edl(&self, segments: &[(impl AsRef<str>, impl AsRef<str>)]) -> Result<()> {
+        self.set_url(&edl_url(segments))
+    }
+}
+
+impl Drop for OnLoad<'_> {
+    fn drop(&mut self) {
+        if self.continue_on_drop {
+            let _ = self.handle.hook_continue(self.hook_id);
+        }
+    }
+}
+
+/// A `memory://` URL of an M3U playlist of `urls`.
+pub(crate) fn playlist_url(urls: &[impl AsRef<str>]) -> Result<String> {
+    let mut playlist = String::from("memory://#EXTM3U\n");
+    for url in urls {
+        let url = url.as_ref();
+        if url.contains(['\n', '\r']) {
+            return Err(Error::InvalidParameter);
+        }
+        playlist.push_str(url);
+        playlist.push('\n');
+    }
+    Ok(playlist)
+}
+
+/// An `edl://` URL of `segments`, with each file name escaped as `%<length>%<name>`.
+pub(crate) fn edl_url(segments: &[(impl AsRef<str>, impl AsRef<str>)]) -> String {
+    let segments: Vec<_> = segments.iter()
+        .map(|(file, params)| format!("%{}%{}{}", file.as_ref().len(), file.as_ref(), params.as_ref()))
+        .collect();
+    format!("edl://{}", segments.join(";"))
+}
@@ -0,0 +1,100 @@
+//! `tracing` instrumentation of calls into libmpv, enabled by the `instrument` feature.
+//!
+//! Spans are created with [`traced!`] at [`DEBUG`](tracing::Level::DEBUG) level, with the target `libmpv_client`.
+//! Each records how long it took in `duration_us`, and the [`Error`] it returned, if any, in `error`.
+
+use std::ffi::CStr;
+use std::time::Instant;
+use tracing::Span;
+use libmpv_client_sys as mpv;
+use libmpv_client_sys::{mpv_event, mpv_format};
+use crate::*;
+
+/// Run `f` inside `span`, recording its duration and error.
+///
+/// `span` must declare the `duration_us` and `error` fields, which [`traced!`] does.
+pub(crate) fn traced<T>(span: Span, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let _entered = span.enter();
+    let start = Instant::now();
+
+    let result = f();
+
+    span.record("duration_us", start.elapsed().as_micros() as u64);
+    if let Err(e) = &result {
+        span.record("error", tracing::field::debug(e));
+    }
+    result
+}
+
+/// Run `wait` (a call to `mpv_wait_event()`) inside an `mpv_wait_event` span, recording its duration and the name of the received event.
+pub(crate) fn wait_event(timeout: f64, wait: impl FnOnce() -> *mut mpv_event) -> *mut mpv_event {
+    let span = tracing::debug_span!(
+        target: "libmpv_client", "mpv_wait_event", timeout,
+        event = tracing::field::Empty, duration_us = tracing::field::Empty,
+    );
+    let _entered = span.enter();
+    let start = Instant::now();
+
+    let event = wait();
+
+    span.record("duration_us", start.elapsed().as_micros() as u64);
+    if let Some(event) = unsafe { event.as_ref() } {
+        let name = unsafe { mpv::event_name(event.event_id) };
+        if !name.is_null() {
+            span.record("event", unsafe { CStr::from_ptr(name) }.to_str().unwrap_or("unknown"));
+        }
+    }
+    event
+}
+
+/// The name of `format`, as used in mpv's documentation.
+pub(crate) fn format_name(format: mpv_format) -> &'static str {
+    match format {
+        f if f == Format::NONE.0 => "none",
+        f if f == Format::STRING.0 => "string",
+        f if f == Format::OSD_STRING.0 => "osd-string",
+        f if f == Format::FLAG.0 => "flag",
+        f if f == Format::INT64.0 => "int64",
+        f if f == Format::DOUBLE.0 => "double",
+        f if f == Format::NODE.0 => "node",
+        f if f == Format::NODE_ARRAY.0 => "node-array",
+        f if f == Format::NODE_MAP.0 => "node-map",
+        f if f == Format::BYTE_ARRAY.0 => "byte-array",
+        _ => "unknown",
+    }
+}
+
+/// The name of the command in a [`Node`] passed to [`Handle::command_node()`]: the first element of an array, or the `name` entry of a map.
+pub(crate) fn command_name(command: &Node) -> &str {
+    let name = match command {
+        Node::Array(args) => args.first(),
+        Node::Map(args) => args.get("name"),
+        _ => None,
+    };
+
+    match name {
+        Some(Node::String(name)) => name,
+        _ => "",
+    }
+}
+
+/// The format of each element of a [`Node`] passed to [`Handle::command_node()`].
+pub(crate) fn node_formats(command: &Node) -> Vec<&'static str> {
+    let format = |node: &Node| match node {
+        Node::None => "none",
+        Node::String(_) | Node::RawString(_) => "string",
+        Node::Flag(_) => "flag",
+        Node::Int64(_) => "int64",
+        Node::Double(_) => "double",
+        Node::Array(_) => "node-array",
+        Node::Map(_) => "node-map",
+        Node::ByteArray(_) => "byte-array",
+        Node::Unknown(_) => "unknown",
+    };
+
+    match command {
+        Node::Array(args) => args.iter().map(format).collect(),
+        Node::Map(args) => args.values().map(format).collect(),
+        node => vec![format(node)],
+    }
+}
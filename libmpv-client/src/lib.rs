@@ -5,6 +5,9 @@
 #[macro_use]
 mod macros;
 
+// Lets the code generated by the derive macros refer to `::libmpv_client` from inside this crate too.
+extern crate self as libmpv_client;
+
 use std::ffi::c_void;
 use libmpv_client_sys as mpv;
 
@@ -21,11 +24,63 @@ pub use event::{Event, EventId};
 pub mod types;
 pub use types::*;
 
+#[cfg(feature = "derive")]
+pub use libmpv_client_derive::{MpvFormat, MpvRecv, MpvSend};
+
+#[cfg(feature = "derive")]
+/// Generate the `mpv_open_cplugin` entry point of a cplugin, which runs the annotated function. Requires the `derive` feature.
+///
+/// The function takes the plugin's [`Handle`] and returns a [`Result<()>`]. Before it is called, the mandatory initial
+/// [`Handle::wait_event()`] lets mpv continue loading; if that already returns [`Event::Shutdown`], the function isn't called at all.
+///
+/// An [`Err`] is returned to mpv as its (negative) error code. A panic never unwinds into mpv: it is reported and then
+/// handled according to the [`PanicPolicy`](panic::PanicPolicy), returning [`Error::Generic`] by default (see [`panic`]).
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+/// #[libmpv_client::cplugin]
+/// fn plugin_main(handle: Handle) -> Result<()> {
+///     loop {
+///         match handle.wait_event(-1.0)? {
+///             Event::Shutdown => return Ok(()),
+///             event => println!("{event:?}"),
+///         }
+///     }
+/// }
+/// ```
+pub use libmpv_client_derive::cplugin;
+
+mod cplugin;
+pub mod panic;
+
+/// Items used by the code generated by `#[derive(MpvFormat, MpvRecv, MpvSend)]` and `#[cplugin]`. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::types::convert::private::*;
+    pub use crate::types::{FromNode, ToNode, Node, NodeMap};
+    pub use crate::error::Result;
+    pub use crate::cplugin::run as run_cplugin;
+}
+
 pub mod error;
 pub use error::{Error, Result};
 use libmpv_client_sys::mpv_node;
 
 pub mod version;
+pub mod ass;
+pub mod overlay;
+pub mod rpc;
+pub mod dispatch;
+pub mod hooks;
+pub mod resolver;
+pub mod observer;
+pub mod logging;
+pub mod state;
+#[cfg(feature = "instrument")]
+mod instrument;
+pub mod subprocess;
+pub mod screenshot;
 mod tests;
 
 pub(crate) unsafe fn mpv_free(data: *mut c_void) {
@@ -0,0 +1,163 @@
+//! Forwarding of mpv's log messages ([`Event::LogMessage`]) to the `log` and `tracing` crates.
+//!
+//! [`to_log()`] (with the `log` feature) and [`to_tracing()`] (with the `tracing` feature) forward a single [`LogMessage`].
+//! They can be called from a [`Dispatcher`](crate::dispatch::Dispatcher) with
+//! [`Dispatcher::forward_log_messages()`](crate::dispatch::Dispatcher::forward_log_messages()),
+//! or from a [`LogForwarder`], which runs on a dedicated client.
+//!
+//! mpv's levels map to the crate's levels as follows:
+//!
+//! | mpv                                   | `log`/`tracing` |
+//! |---------------------------------------|-----------------|
+//! | [`Fatal`](LogLevel::Fatal), [`Error`](LogLevel::Error) | `ERROR` |
+//! | [`Warn`](LogLevel::Warn)              | `WARN`          |
+//! | [`Info`](LogLevel::Info)              | `INFO`          |
+//! | [`Verbose`](LogLevel::Verbose)        | `DEBUG`         |
+//! | [`Debug`](LogLevel::Debug), [`Trace`](LogLevel::Trace) | `TRACE` |
+//!
+//! As a special case, a message with the prefix `overflow` means that mpv dropped log messages, and is reported as a warning.
+
+use crate::*;
+use crate::event::{LogLevel, LogMessage};
+
+/// The target of the warning about dropped log messages, and the prefix of every other target.
+pub const TARGET: &str = "mpv";
+
+/// The prefix mpv uses for its message about dropped log messages.
+#[cfg(any(feature = "log", feature = "tracing"))]
+pub(crate) const OVERFLOW_PREFIX: &str = "overflow";
+
+/// The text of `message`, without its trailing newline.
+#[cfg(any(feature = "log", feature = "tracing"))]
+pub(crate) fn text(message: &LogMessage) -> &str {
+    message.text.strip_suffix('\n').unwrap_or(&message.text)
+}
+
+/// Report an `error` which can't be returned to anyone, such as a failure inside a handler which has to keep running.
+///
+/// It is logged at error level with the target `libmpv_client` if the `tracing` or `log` feature is enabled,
+/// and otherwise printed through mpv with the `print-text` command.
+#[cfg_attr(any(feature = "log", feature = "tracing"), allow(unused_variables))]
+pub(crate) fn report_error(handle: &Handle, context: &str, error: &Error) {
+    #[cfg(feature = "tracing")]
+    tracing::error!(target: "libmpv_client", "{context}: {error:?}");
+    #[cfg(all(feature = "log", not(feature = "tracing")))]
+    log::error!(target: "libmpv_client", "{context}: {error:?}");
+    #[cfg(not(any(feature = "log", feature = "tracing")))]
+    let _ = handle.command(&["print-text", &format!("{context}: {error:?}")]);
+}
+
+/// Forward `message` to the `log` crate, with a target of `mpv::<prefix>` (such as `mpv::ffmpeg`). Requires the `log` feature.
+#[cfg(feature = "log")]
+pub fn to_log(message: &LogMessage) {
+    use ::log::Level;
+
+    if message.prefix == OVERFLOW_PREFIX {
+        ::log::warn!(target: TARGET, "mpv dropped log messages: {}", text(message));
+        return;
+    }
+
+    let level = match message.level {
+        LogLevel::None => return,
+        LogLevel::Fatal | LogLevel::Error => Level::Error,
+        LogLevel::Warn => Level::Warn,
+        LogLevel::Info => Level::Info,
+        LogLevel::Verbose => Level::Debug,
+        LogLevel::Debug | LogLevel::Trace => Level::Trace,
+    };
+
+    ::log::log!(target: &format!("{TARGET}::{}", message.prefix), level, "{}", text(message));
+}
+
+/// Forward `message` to the `tracing` crate. Requires the `tracing` feature.
+///
+/// `tracing` only supports static targets, so every event has the target `mpv`, and the prefix is recorded in its `prefix` field.
+#[cfg(feature = "tracing")]
+pub fn to_tracing(message: &LogMessage) {
+    use ::tracing::{debug, error, info, trace, warn};
+
+    let prefix = message.prefix.as_str();
+    let text = text(message);
+
+    if prefix == OVERFLOW_PREFIX {
+        warn!(target: TARGET, "mpv dropped log messages: {text}");
+        return;
+    }
+
+    match message.level {
+        LogLevel::None => {}
+        LogLevel::Fatal | LogLevel::Error => error!(target: TARGET, prefix, "{text}"),
+        LogLevel::Warn => warn!(target: TARGET, prefix, "{text}"),
+        LogLevel::Info => info!(target: TARGET, prefix, "{text}"),
+        LogLevel::Verbose => debug!(target: TARGET, prefix, "{text}"),
+        LogLevel::Debug | LogLevel::Trace => trace!(target: TARGET, prefix, "{text}"),
+    }
+}
+
+/// Forwards log messages on its own weak [`Client`], so they never mix with the events of the [`Handle`] it was created from.
+///
+/// Messages are only forwarded while [`LogForwarder::poll()`] or [`LogForwarder::run()`] is running.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::event::{LogLevel, LogMessage};
+///# use libmpv_client::logging::LogForwarder;
+///#
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// let mut forwarder = LogForwarder::new(&handle, "my-plugin-log", LogLevel::Info, |message: &LogMessage| {
+///     // or `libmpv_client::logging::to_log`, with the `log` feature
+///     eprintln!("[{}] {}", message.prefix, message.text.trim_end());
+/// })?;
+///
+/// // Forward messages until mpv shuts down.
+/// forwarder.run()?;
+///#     Ok(())
+///# }
+/// ```
+pub struct LogForwarder<F> {
+    client: Client,
+    forward: F,
+    shutdown: bool,
+}
+
+impl<F: FnMut(&LogMessage)> LogForwarder<F> {
+    /// Create a new weak [`Client`] named `name` on the same core as `handle`, receiving messages up to `max_level`,
+    /// and pass them to `forward`, such as [`to_log()`] or [`to_tracing()`].
+    pub fn new(handle: &Handle, name: &str, max_level: LogLevel, forward: F) -> Result<Self> {
+        let client = handle.create_weak_client(name)?;
+        client.request_log_messages(max_level)?;
+
+        Ok(Self { client, forward, shutdown: false })
+    }
+
+    /// The [`Client`] used to receive log messages.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Wait up to `timeout` seconds for one event and forward it if it is a log message.
+    ///
+    /// `timeout` has the same meaning as in [`Handle::wait_event()`].
+    /// Returns `false` once mpv is shutting down, after which the [`LogForwarder`] should be dropped.
+    pub fn poll(&mut self, timeout: f64) -> Result<bool> {
+        if self.shutdown {
+            return Ok(false);
+        }
+
+        match self.client.wait_event(timeout)? {
+            Event::Shutdown => self.shutdown = true,
+            Event::LogMessage(message) => (self.forward)(&message),
+            _ => {}
+        }
+
+        Ok(!self.shutdown)
+    }
+
+    /// Forward log messages until mpv shuts down.
+    pub fn run(&mut self) -> Result<()> {
+        while self.poll(-1.0)? {}
+        Ok(())
+    }
+}
@@ -31,6 +31,28 @@ macro_rules! check_null {
     };
 }
 
+/// Evaluate `$body` (a [`Result`](crate::Result)) inside a `tracing` span named `$name` with the given fields,
+/// recording its duration and error. Without the `instrument` feature, this only evaluates `$body`.
+#[cfg(feature = "instrument")]
+macro_rules! traced {
+    ($name:literal, [$($field:tt)*], $body:expr) => {
+        $crate::instrument::traced(
+            ::tracing::debug_span!(
+                target: "libmpv_client", $name, $($field)*,
+                duration_us = ::tracing::field::Empty, error = ::tracing::field::Empty,
+            ),
+            || $body,
+        )
+    };
+}
+
+#[cfg(not(feature = "instrument"))]
+macro_rules! traced {
+    ($name:literal, [$($field:tt)*], $body:expr) => {
+        $body
+    };
+}
+
 #[macro_export]
 /// Construct a [`NodeArray`](crate::NodeArray) from a list of items that implement [`Into<Node>`].
 ///
@@ -64,7 +86,6 @@ macro_rules! node_array {
 ///
 /// # Example
 /// ```
-///# use std::collections::HashMap;
 ///# use libmpv_client::{node_map, Node, NodeMap};
 /// let array_node = node_map! {
 ///     ("1", "one"),
@@ -72,7 +93,7 @@ macro_rules! node_array {
 ///     ("pi", 3.14),
 /// };
 ///
-/// let test_array_node = Node::Map(HashMap::from([
+/// let test_array_node = Node::Map(NodeMap::from([
 ///     ("1".to_string(), Node::String("one".to_string())),
 ///     ("two".to_string(), Node::Int64(2)),
 ///     ("pi".to_string(), Node::Double(3.14))
@@ -84,6 +105,6 @@ macro_rules! node_map {
     (
         $(($key:expr, $val:expr)),*$(,)?
     ) => {
-        $crate::Node::Map(std::collections::HashMap::from([$(($key.to_string(), $crate::Node::from($val)),)*]))
+        $crate::Node::Map($crate::NodeMap::from([$(($key.to_string(), $crate::Node::from($val)),)*]))
     };
 }
\ No newline at end of file
@@ -0,0 +1,116 @@
+//! Typed property observers, created with [`Handle::observe()`] and [`Handle::observe_changes()`].
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::*;
+use crate::event::{PropertyChange, PropertyValue};
+use crate::types::traits::{MpvRecvInternal, MpvSendInternal};
+
+/// `userdata` values allocated by this crate have the highest bit set, so they never collide with small, hand-picked values.
+static NEXT_USERDATA: AtomicU64 = AtomicU64::new(1 << 63);
+
+/// Allocate a `userdata` value which is unique within the process.
+pub(crate) fn next_userdata() -> u64 {
+    NEXT_USERDATA.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A property observed with [`Handle::observe()`] or [`Handle::observe_changes()`], which is unobserved once dropped.
+///
+/// Changes still arrive as [`Event::PropertyChange`]; the [`Observer`] recognizes its own by their `userdata`.
+pub struct Observer<'h, T> {
+    handle: &'h Handle,
+    name: String,
+    userdata: u64,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<'h, T> Observer<'h, T> {
+    pub(crate) fn new(handle: &'h Handle, name: &str, format: Format) -> Result<Self> {
+        let userdata = next_userdata();
+        handle.observe_property(name, format, userdata)?;
+
+        Ok(Self { handle, name: name.to_string(), userdata, _type: PhantomData })
+    }
+
+    /// The name of the observed property.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The `userdata` value allocated for this observer.
+    pub fn userdata(&self) -> u64 {
+        self.userdata
+    }
+
+    /// Whether `change` is a change of this observer's property.
+    pub fn matches(&self, change: &PropertyChange) -> bool {
+        change.userdata == self.userdata
+    }
+}
+
+impl<T: MpvRecv> Observer<'_, T> {
+    /// Read the current value of the property with [`Handle::get_property()`], or `Ok(None)` if it is unavailable.
+    ///
+    /// Use this to catch up after an [`Event::QueueOverflow`], when changes may have been dropped.
+    pub fn fetch(&self) -> Result<Option<T>> {
+        match self.handle.get_property::<T>(&self.name) {
+            Ok(value) => Ok(Some(value)),
+            Err(Error::PropertyUnavailable | Error::PropertyNotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Decode the new value from `change`, or return [`None`] if it belongs to another observer.
+    ///
+    /// The value is `Ok(None)` if the property is unavailable ([`PropertyValue::None`]).
+    pub fn value(&self, change: &PropertyChange) -> Option<Result<Option<T>>> {
+        if !self.matches(change) {
+            return None;
+        }
+
+        Some(match &change.value {
+            Ok(value) => decode(value),
+            Err(e) => Err(e.clone()),
+        })
+    }
+}
+
+impl<T> Drop for Observer<'_, T> {
+    fn drop(&mut self) {
+        let _ = self.handle.unobserve_property(self.userdata);
+    }
+}
+
+/// Convert an already received [`PropertyValue`] into a `T`, by passing it through its mpv representation again.
+///
+/// This is only done for values which were requested in `T`'s [`Format`], so the representation always matches.
+pub(crate) fn decode<T: MpvRecv>(value: &PropertyValue) -> Result<Option<T>> {
+    match value {
+        PropertyValue::None => Ok(None),
+        PropertyValue::String(v) => round_trip(v).map(Some),
+        PropertyValue::OsdString(v) => round_trip(v).map(Some),
+        PropertyValue::Flag(v) => round_trip(v).map(Some),
+        PropertyValue::Int64(v) => round_trip(v).map(Some),
+        PropertyValue::Double(v) => round_trip(v).map(Some),
+        PropertyValue::Node(v) => round_trip(v).map(Some),
+        PropertyValue::NodeArray(v) => round_trip(v).map(Some),
+        PropertyValue::NodeMap(v) => round_trip(v).map(Some),
+        PropertyValue::ByteArray(v) => round_trip(v).map(Some),
+        PropertyValue::Unknown(_) => Err(Error::PropertyFormat),
+    }
+}
+
+fn round_trip<V: MpvSendInternal, T: MpvRecvInternal>(value: &V) -> Result<T> {
+    if V::MPV_FORMAT.0 != T::MPV_FORMAT.0 {
+        return Err(Error::PropertyFormat);
+    }
+
+    let result = Cell::new(None);
+    value.to_mpv(|ptr| {
+        result.set(Some(unsafe { T::from_ptr(ptr) }));
+        Ok(0)
+    })?;
+
+    result.into_inner().unwrap_or(Err(Error::PropertyFormat))
+}
@@ -0,0 +1,199 @@
+//! Raw bitmap overlays shown with mpv's [`overlay-add`](https://mpv.io/manual/stable/#command-interface-overlay-add) command.
+//!
+//! `overlay-add` can read a BGRA bitmap directly from a memory address, which saves going through temporary files
+//! for images rendered in Rust. [`BitmapOverlay`] owns such a bitmap and keeps it alive until the overlay is removed.
+
+use crate::*;
+use crate::ass::Color;
+
+/// The highest overlay ID accepted by mpv.
+pub const MAX_OVERLAY_ID: u8 = 63;
+
+/// A BGRA bitmap shown on top of the video with [`overlay-add`](https://mpv.io/manual/stable/#command-interface-overlay-add).
+///
+/// The pixels are owned by the [`BitmapOverlay`] in a heap allocation which never moves or reallocates,
+/// and its address is passed to mpv as `&<address>`. The memory is kept alive until the overlay is removed,
+/// either with [`BitmapOverlay::hide()`] or when the [`BitmapOverlay`] is dropped.
+///
+/// Pixels are stored as `[B, G, R, A]` with premultiplied alpha, as required by mpv's `bgra` format.
+/// [`BitmapOverlay::set_pixel()`] and [`BitmapOverlay::fill_from_rgba()`] convert from straight (non-premultiplied) alpha.
+///
+/// Changes to the pixels, position or display size are only shown after calling [`BitmapOverlay::show()`] again.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::ass::Color;
+///# use libmpv_client::overlay::BitmapOverlay;
+///#
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// let mut waveform = BitmapOverlay::new(&handle, 0, 400, 100)?;
+/// waveform.set_position(20, 600);
+///
+/// for x in 0..400 {
+///     waveform.set_pixel(x, 50, Color::rgba(255, 255, 255, 200));
+/// }
+///
+/// waveform.show()?;
+/// // The overlay is removed once `waveform` is dropped.
+///#     Ok(())
+///# }
+/// ```
+pub struct BitmapOverlay<'a> {
+    handle: &'a Handle,
+    id: u8,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    display_size: Option<(u32, u32)>,
+    pixels: Box<[[u8; 4]]>,
+    visible: bool,
+}
+
+impl<'a> BitmapOverlay<'a> {
+    /// Create a fully transparent overlay of `width` by `height` pixels, positioned at `(0, 0)`.
+    ///
+    /// `id` identifies the overlay to mpv and must be between `0` and [`MAX_OVERLAY_ID`], otherwise [`Error::InvalidParameter`] is returned.
+    /// Using the same `id` for two overlays at once will make them replace each other.
+    ///
+    /// The overlay is not shown until [`BitmapOverlay::show()`] is called.
+    pub fn new(handle: &'a Handle, id: u8, width: u32, height: u32) -> Result<Self> {
+        if id > MAX_OVERLAY_ID || width == 0 || height == 0 {
+            return Err(Error::InvalidParameter);
+        }
+
+        Ok(Self {
+            handle,
+            id,
+            x: 0,
+            y: 0,
+            width,
+            height,
+            display_size: None,
+            pixels: vec![[0; 4]; width as usize * height as usize].into_boxed_slice(),
+            visible: false,
+        })
+    }
+
+    /// The overlay ID passed to mpv.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// The width of the bitmap in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the bitmap in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Whether the overlay is currently shown.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Set the position of the top-left corner of the overlay, in OSD coordinates (see the `osd-width` and `osd-height` properties).
+    pub fn set_position(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Scale the overlay to `width` by `height` pixels when shown, or show it at its native size with [`None`].
+    ///
+    /// This requires mpv 0.37 or newer.
+    pub fn set_display_size(&mut self, size: Option<(u32, u32)>) {
+        self.display_size = size;
+    }
+
+    /// The raw pixels, row by row, as premultiplied `[B, G, R, A]`.
+    pub fn pixels(&self) -> &[[u8; 4]] {
+        &self.pixels
+    }
+
+    /// Mutable access to the raw pixels, row by row, as premultiplied `[B, G, R, A]`.
+    pub fn pixels_mut(&mut self) -> &mut [[u8; 4]] {
+        &mut self.pixels
+    }
+
+    /// Set a single pixel. Pixels outside the bitmap are ignored.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        if x < self.width && y < self.height {
+            self.pixels[y as usize * self.width as usize + x as usize] = premultiply(color);
+        }
+    }
+
+    /// Set every pixel to the same color.
+    pub fn fill(&mut self, color: Color) {
+        self.pixels.fill(premultiply(color));
+    }
+
+    /// Replace the whole bitmap with `rgba`, a packed buffer of straight-alpha `[R, G, B, A]` pixels.
+    ///
+    /// Returns [`Error::InvalidParameter`] if `rgba` is not exactly `width * height * 4` bytes long.
+    pub fn fill_from_rgba(&mut self, rgba: &[u8]) -> Result<()> {
+        if rgba.len() != self.pixels.len() * 4 {
+            return Err(Error::InvalidParameter);
+        }
+
+        for (pixel, src) in self.pixels.iter_mut().zip(rgba.chunks_exact(4)) {
+            *pixel = premultiply(Color::rgba(src[0], src[1], src[2], src[3]));
+        }
+
+        Ok(())
+    }
+
+    /// Show the overlay, or update it if it is already shown.
+    ///
+    /// mpv copies the bitmap during this call, so this must be called again after changing the pixels, position or display size.
+    pub fn show(&mut self) -> Result<()> {
+        let mut command = node_map! {
+            ("name", "overlay-add"),
+            ("id", self.id as i64),
+            ("x", self.x as i64),
+            ("y", self.y as i64),
+            ("file", format!("&{}", self.pixels.as_ptr() as usize)),
+            ("offset", 0),
+            ("fmt", "bgra"),
+            ("w", self.width as i64),
+            ("h", self.height as i64),
+            ("stride", self.width as i64 * 4),
+        };
+
+        if let (Node::Map(map), Some((dw, dh))) = (&mut command, self.display_size) {
+            map.insert("dw".to_string(), Node::Int64(dw as i64));
+            map.insert("dh".to_string(), Node::Int64(dh as i64));
+        }
+
+        self.handle.command_node(command)?;
+        self.visible = true;
+        Ok(())
+    }
+
+    /// Remove the overlay with [`overlay-remove`](https://mpv.io/manual/stable/#command-interface-overlay-remove).
+    ///
+    /// The pixels are kept, and the overlay can be shown again with [`BitmapOverlay::show()`].
+    pub fn hide(&mut self) -> Result<()> {
+        if self.visible {
+            self.handle.command(&["overlay-remove", &self.id.to_string()])?;
+            self.visible = false;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for BitmapOverlay<'_> {
+    fn drop(&mut self) {
+        let _ = self.hide();
+    }
+}
+
+/// Convert a straight-alpha [`Color`] into a premultiplied `[B, G, R, A]` pixel.
+pub(crate) fn premultiply(color: Color) -> [u8; 4] {
+    let mul = |c: u8| ((c as u16 * color.a as u16 + 127) / 255) as u8;
+    [mul(color.b), mul(color.g), mul(color.r), color.a]
+}
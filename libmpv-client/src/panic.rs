@@ -0,0 +1,171 @@
+//! Containment of panics at the FFI boundary.
+//!
+//! Unwinding out of an `extern "C"` function into mpv is undefined behavior, so every function this crate hands to mpv
+//! (the `mpv_open_cplugin` generated by [`#[cplugin]`](crate::cplugin), and the callback of [`Handle::set_wakeup_callback()`])
+//! runs inside a guard which catches panics.
+//!
+//! A panic which reaches a guard is reported to the [panic hook](set_panic_hook()) once it is caught, while the [`Handle`] is still usable.
+//! By default, it is printed through mpv with the `print-text` command, prefixed with the client's name.
+//! mpv prints such text itself, at info level, so it isn't affected by the client's `msg-level`.
+//! Afterwards, the [`PanicPolicy`] decides whether the process aborts, or the guard recovers and returns an error to mpv.
+//! Panics caught before reaching a guard, such as with [`catch_unwind`] inside `main`, are not reported.
+//!
+//! This is separate from the process-wide hook of [`std::panic::set_hook()`], which still runs first for every panic,
+//! so Rust's default hook also prints guarded panics to stderr. The first guard wraps the hook installed at that point
+//! to record where a guarded panic happened ([`PanicReport::location`]). Replacing the process-wide hook afterwards doesn't stop
+//! panics from being reported, but their location is then unknown.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::mem::ManuallyDrop;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Once, RwLock};
+use crate::*;
+
+/// What a guard does once it caught a panic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Return an error to mpv, such as [`Error::Generic`] from `mpv_open_cplugin`, or nothing from a callback. This is the default.
+    #[default]
+    Recover,
+    /// Abort the whole process, including mpv.
+    Abort,
+}
+
+/// A panic caught at the FFI boundary, as passed to the [panic hook](set_panic_hook()).
+pub struct PanicReport<'a> {
+    /// The function mpv called, such as `mpv_open_cplugin` or `wakeup callback`.
+    pub boundary: &'static str,
+    /// The panic message, if it was a string.
+    pub message: &'a str,
+    /// Where the panic happened, as `file:line:column`, or [`None`] if it is unknown.
+    pub location: Option<&'a str>,
+    handle: Option<ManuallyDrop<Handle>>,
+}
+
+impl PanicReport<'_> {
+    /// The [`Handle`] the boundary belongs to, if the mpv API may be called from it.
+    ///
+    /// This is [`None`] in callbacks such as the wakeup callback, where mpv forbids any API calls.
+    pub fn handle(&self) -> Option<&Handle> {
+        self.handle.as_deref()
+    }
+}
+
+type PanicHook = Box<dyn Fn(&PanicReport) + Send + Sync>;
+
+static ABORT: AtomicBool = AtomicBool::new(false);
+static HOOK: RwLock<Option<PanicHook>> = RwLock::new(None);
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    /// The innermost guard on this thread, and its `mpv_handle` (null if the API must not be called).
+    pub(crate) static BOUNDARY: Cell<Option<(&'static str, *mut mpv_handle)>> = const { Cell::new(None) };
+    /// The location of the last panic inside a guard on this thread, recorded by the process-wide panic hook.
+    static LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Set what guards do once they caught a panic.
+pub fn set_panic_policy(policy: PanicPolicy) {
+    ABORT.store(policy == PanicPolicy::Abort, Ordering::Relaxed);
+}
+
+/// The current [`PanicPolicy`].
+pub fn panic_policy() -> PanicPolicy {
+    if ABORT.load(Ordering::Relaxed) { PanicPolicy::Abort } else { PanicPolicy::Recover }
+}
+
+/// Report panics caught by guards to `hook` instead of [`default_panic_hook()`].
+///
+/// `hook` runs once a guard caught the panic, before the [`PanicPolicy`] is applied, and must not panic itself.
+/// It doesn't replace the process-wide hook of [`std::panic::set_hook()`], which runs first, when the panic starts.
+pub fn set_panic_hook(hook: impl Fn(&PanicReport) + Send + Sync + 'static) {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(hook));
+}
+
+/// Go back to reporting panics with [`default_panic_hook()`].
+pub fn reset_panic_hook() {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Print `report` through mpv with the `print-text` command, prefixed with the client's name, or to stderr if the mpv API can't be called.
+///
+/// mpv prints the text at info level, under its own name rather than the client's.
+pub fn default_panic_hook(report: &PanicReport) {
+    let text = match report.location {
+        Some(location) => format!("panic in {} at {location}: {}", report.boundary, report.message),
+        None => format!("panic in {}: {}", report.boundary, report.message),
+    };
+
+    match report.handle() {
+        Some(handle) => {
+            let text = match handle.client_name() {
+                Ok(name) => format!("[{name}] {text}"),
+                Err(_) => text,
+            };
+            if handle.command(&["print-text", &text]).is_err() {
+                eprintln!("{text}");
+            }
+        }
+        None => eprintln!("{text}"),
+    }
+}
+
+/// Report a panic caught by the guard named `boundary`, with its `payload`.
+fn report(boundary: &'static str, handle: *mut mpv_handle, payload: &(dyn Any + Send)) {
+    let message = payload.downcast_ref::<&str>().copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let location = LOCATION.take();
+
+    let report = PanicReport {
+        boundary,
+        message,
+        location: location.as_deref(),
+        handle: (!handle.is_null()).then(|| ManuallyDrop::new(Handle::from_ptr(handle))),
+    };
+
+    match &*HOOK.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(hook) => hook(&report),
+        None => default_panic_hook(&report),
+    }
+}
+
+/// Run `f` at the FFI boundary named `boundary`, containing any panic according to the [`PanicPolicy`].
+///
+/// `handle` is passed to the panic hook, and must be null if the mpv API must not be called from here.
+/// If a panic is recovered from, the result of `recover` is returned instead.
+pub(crate) fn guard<R>(boundary: &'static str, handle: *mut mpv_handle, recover: impl FnOnce() -> R, f: impl FnOnce() -> R) -> R {
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if BOUNDARY.get().is_some() {
+                LOCATION.set(info.location().map(ToString::to_string));
+            }
+            previous(info);
+        }));
+    });
+
+    let outer = BOUNDARY.replace(Some((boundary, handle)));
+    LOCATION.take();
+    let result = catch_unwind(AssertUnwindSafe(f));
+    BOUNDARY.set(outer);
+
+    match result {
+        Ok(value) => value,
+        Err(payload) => {
+            report(boundary, handle, &*payload);
+            match panic_policy() {
+                PanicPolicy::Abort => std::process::abort(),
+                PanicPolicy::Recover => recover(),
+            }
+        }
+    }
+}
+
+/// Run `f` at a boundary where the mpv API must not be called, such as a wakeup callback.
+pub(crate) fn guard_callback(boundary: &'static str, f: impl FnOnce()) {
+    guard(boundary, null_mut(), || (), f)
+}
@@ -0,0 +1,251 @@
+//! URL resolvers, which let mpv play URLs it doesn't understand by translating them in the [`ON_LOAD`](hooks::ON_LOAD) hook.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::*;
+use crate::dispatch::Dispatcher;
+use crate::hooks::{self, OnLoad};
+use crate::logging::report_error;
+
+/// Translates URLs of some scheme or site into something mpv can play.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::resolver::{Resolution, Resolver};
+///#
+/// struct Catalog;
+///
+/// impl Resolver for Catalog {
+///     fn matches(&self, url: &str) -> bool {
+///         url.starts_with("catalog://")
+///     }
+///
+///     fn resolve(&self, url: &str) -> Result<Resolution> {
+///         let id = &url["catalog://".len()..];
+///         Ok(Resolution::Url(format!("https://media.example/{id}/master.m3u8")))
+///     }
+/// }
+/// ```
+pub trait Resolver {
+    /// Whether this resolver handles `url`.
+    fn matches(&self, url: &str) -> bool;
+
+    /// Translate `url`, which this resolver [`matches()`](Resolver::matches()).
+    ///
+    /// An error is reported (see [`ResolverRegistry`]) and the URL is left as it is, so mpv usually fails to open the file.
+    fn resolve(&self, url: &str) -> Result<Resolution>;
+}
+
+/// What a [`Resolver`] translated a URL into.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Resolution {
+    /// Open this URL instead.
+    Url(String),
+    /// Open a main stream, and add separate audio and subtitle streams once it is loaded.
+    Streams(Streams),
+    /// Replace the URL with a playlist of these URLs.
+    Playlist(Vec<String>),
+}
+
+/// Separate streams for a single file, returned as [`Resolution::Streams`].
+#[derive(Debug, Clone, Default)]
+pub struct Streams {
+    /// The URL opened as the file, usually the video stream.
+    pub video: String,
+    /// Audio streams, added with the `audio-add` command.
+    pub audio: Vec<ExternalTrack>,
+    /// Subtitle streams, added with the `sub-add` command.
+    pub subtitles: Vec<ExternalTrack>,
+}
+
+/// An audio or subtitle stream added to a file.
+#[derive(Debug, Clone, Default)]
+pub struct ExternalTrack {
+    /// The URL of the stream.
+    pub url: String,
+    /// The title shown for the track.
+    pub title: Option<String>,
+    /// The language of the track, such as `en`.
+    pub lang: Option<String>,
+}
+
+impl ExternalTrack {
+    /// Arguments for `command` (`audio-add` or `sub-add`). The track is selected automatically, as with any other track.
+    pub(crate) fn command<'t>(&'t self, command: &'t str) -> Vec<&'t str> {
+        let mut args = vec![command, &self.url, "auto"];
+        match (&self.title, &self.lang) {
+            (title, Some(lang)) => args.extend([title.as_deref().unwrap_or(""), lang]),
+            (Some(title), None) => args.push(title),
+            (None, None) => {}
+        }
+        args
+    }
+}
+
+pub(crate) type BoxedResolver<'a> = Box<dyn Resolver + 'a>;
+
+/// Resolvers ordered by priority, lowest first.
+#[derive(Default)]
+pub(crate) struct Resolvers<'a>(Vec<(i32, BoxedResolver<'a>)>);
+
+impl<'a> Resolvers<'a> {
+    pub(crate) fn add(&mut self, priority: i32, resolver: BoxedResolver<'a>) {
+        // Resolvers with the same priority keep the order they were added in.
+        let index = self.0.partition_point(|(p, _)| *p <= priority);
+        self.0.insert(index, (priority, resolver));
+    }
+
+    pub(crate) fn find(&self, url: &str) -> Option<&dyn Resolver> {
+        self.0.iter().map(|(_, resolver)| resolver.as_ref()).find(|resolver| resolver.matches(url))
+    }
+}
+
+/// A set of [`Resolver`]s, installed into a [`Dispatcher`] with [`ResolverRegistry::install()`].
+///
+/// For each file, the first resolver (by priority) which [`matches()`](Resolver::matches()) its URL resolves it.
+/// Fallback resolvers only run in the [`ON_LOAD_FAIL`](hooks::ON_LOAD_FAIL) hook, after mpv failed to open a URL itself.
+///
+/// Errors from resolvers, or from adding their audio and subtitle streams, only affect the file being loaded.
+/// They don't stop the [`Dispatcher`], and are logged at error level with the `log` or `tracing` feature, or printed through mpv otherwise.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::dispatch::Dispatcher;
+///# use libmpv_client::resolver::{Resolution, Resolver, ResolverRegistry};
+///#
+///# struct Catalog;
+///# impl Resolver for Catalog {
+///#     fn matches(&self, url: &str) -> bool { url.starts_with("catalog://") }
+///#     fn resolve(&self, url: &str) -> Result<Resolution> { Ok(Resolution::Url(url.to_string())) }
+///# }
+///#
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+/// let mut dispatcher = Dispatcher::new(Handle::from_ptr(ptr));
+///
+/// let mut resolvers = ResolverRegistry::new();
+/// resolvers.add(0, Catalog);
+/// resolvers.install(&mut dispatcher, 10)?;
+///
+/// dispatcher.run()?;
+///#     Ok(())
+///# }
+/// ```
+#[derive(Default)]
+pub struct ResolverRegistry<'a> {
+    resolvers: Resolvers<'a>,
+    fallbacks: Resolvers<'a>,
+}
+
+impl<'a> ResolverRegistry<'a> {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a `resolver`, which is tried before mpv opens a URL. Resolvers with a lower `priority` are tried first.
+    pub fn add(&mut self, priority: i32, resolver: impl Resolver + 'a) {
+        self.resolvers.add(priority, Box::new(resolver));
+    }
+
+    /// Add a fallback `resolver`, which is only tried after mpv failed to open a URL. Resolvers with a lower `priority` are tried first.
+    pub fn add_fallback(&mut self, priority: i32, resolver: impl Resolver + 'a) {
+        self.fallbacks.add(priority, Box::new(resolver));
+    }
+
+    /// Register the [`ON_LOAD`](hooks::ON_LOAD), [`ON_LOAD_FAIL`](hooks::ON_LOAD_FAIL) and [`ON_PRELOADED`](hooks::ON_PRELOADED) hooks
+    /// with the given `priority` (see [`Handle::hook_add()`]) on `dispatcher`.
+    pub fn install(self, dispatcher: &mut Dispatcher<'a>, priority: i32) -> Result<()> {
+        let Self { resolvers, fallbacks } = self;
+        // Streams of the file being loaded, added once it is preloaded.
+        let pending = Rc::new(RefCell::new(None::<Streams>));
+
+        let on_load_pending = Rc::clone(&pending);
+        dispatcher.on_load(priority, move |on_load| {
+            on_load_pending.replace(None);
+            load(&resolvers, on_load, &on_load_pending)
+        })?;
+
+        let on_load_fail_pending = Rc::clone(&pending);
+        dispatcher.on_hook(hooks::ON_LOAD_FAIL, priority, move |handle, hook| {
+            load(&fallbacks, &OnLoad::borrowed(handle, hook), &on_load_fail_pending)
+        })?;
+
+        dispatcher.on_hook(hooks::ON_PRELOADED, priority, move |handle, _| {
+            let Some(streams) = pending.take() else {
+                return Ok(());
+            };
+            for (command, tracks) in [("audio-add", &streams.audio), ("sub-add", &streams.subtitles)] {
+                for track in tracks {
+                    if let Err(e) = handle.command(&track.command(command)) {
+                        report_error(handle, &format!("failed to add {}", track.url), &e);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+/// The file being loaded, which a [`Resolution`] is applied to. This is an [`OnLoad`], or a stand-in in tests.
+pub(crate) trait LoadTarget {
+    fn url(&self) -> Result<String>;
+    fn set_url(&self, url: &str) -> Result<()>;
+    fn add_playlist(&self, urls: &[String]) -> Result<()>;
+    fn report(&self, context: &str, error: &Error);
+}
+
+impl LoadTarget for OnLoad<'_> {
+    fn url(&self) -> Result<String> {
+        OnLoad::url(self)
+    }
+
+    fn set_url(&self, url: &str) -> Result<()> {
+        OnLoad::set_url(self, url)
+    }
+
+    fn add_playlist(&self, urls: &[String]) -> Result<()> {
+        OnLoad::add_playlist(self, urls)
+    }
+
+    fn report(&self, context: &str, error: &Error) {
+        report_error(self.handle(), context, error);
+    }
+}
+
+/// Resolve the URL of `target` with the first matching resolver, if any, reporting any error instead of returning it.
+pub(crate) fn load(resolvers: &Resolvers<'_>, target: &impl LoadTarget, pending: &RefCell<Option<Streams>>) -> Result<()> {
+    let url = match target.url() {
+        Ok(url) => url,
+        Err(e) => {
+            target.report("failed to read the URL of the file being loaded", &e);
+            return Ok(());
+        }
+    };
+
+    if let Err(e) = resolve(resolvers, &url, target, pending) {
+        target.report(&format!("failed to resolve {url}"), &e);
+    }
+    Ok(())
+}
+
+/// Resolve `url` with the first matching resolver, if any, and apply the result to `target`.
+fn resolve(resolvers: &Resolvers<'_>, url: &str, target: &impl LoadTarget, pending: &RefCell<Option<Streams>>) -> Result<()> {
+    let Some(resolver) = resolvers.find(url) else {
+        return Ok(());
+    };
+
+    match resolver.resolve(url)? {
+        Resolution::Url(url) => target.set_url(&url),
+        Resolution::Streams(streams) => {
+            target.set_url(&streams.video)?;
+            pending.replace(Some(streams));
+            Ok(())
+        }
+        Resolution::Playlist(urls) => target.add_playlist(&urls),
+    }
+}
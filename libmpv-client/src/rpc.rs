@@ -0,0 +1,251 @@
+//! Request/response calls between clients, built on top of [`script-message-to`](https://mpv.io/manual/stable/#command-interface-script-message-to[-]]]).
+//!
+//! [`Event::ClientMessage`] only carries a list of strings. [`Rpc`] layers a small protocol on top of it,
+//! so that Rust plugins and Lua (or JavaScript) scripts can call each other's methods and wait for the result.
+//!
+//! # Protocol
+//! A request is sent to the target client as:
+//!
+//! `script-message-to <target> rpc-request <id> <reply-to> <method> <args>`
+//!
+//! - `id` is a correlation id chosen by the caller, which is echoed back in the reply.
+//! - `reply-to` is where the reply should be sent. [`Rpc`] uses its client ID in the form `@<id>` (see [`Handle::client_id()`]),
+//!   but any name accepted by `script-message-to` works, such as a Lua script's name.
+//! - `args` is a single JSON value, written in mpv's own JSON dialect (the same one as `utils.format_json()`).
+//!
+//! The target answers with exactly one of:
+//!
+//! `script-message-to <reply-to> rpc-reply <id> <result>`, where `result` is a JSON value, or
+//!
+//! `script-message-to <reply-to> rpc-error <id> <message>`.
+//!
+//! # Lua
+//! A Lua script can serve methods to Rust with:
+//! ```lua
+//! local utils = require("mp.utils")
+//! local methods = {
+//!     add = function(args) return args.a + args.b end,
+//! }
+//!
+//! mp.register_script_message("rpc-request", function(id, reply_to, method, args)
+//!     local ok, result = pcall(methods[method], utils.parse_json(args))
+//!     if ok then
+//!         mp.commandv("script-message-to", reply_to, "rpc-reply", id, utils.format_json(result))
+//!     else
+//!         mp.commandv("script-message-to", reply_to, "rpc-error", id, tostring(result))
+//!     end
+//! end)
+//! ```
+//!
+//! And call methods served by Rust with:
+//! ```lua
+//! local utils = require("mp.utils")
+//! local next_id, pending = 0, {}
+//!
+//! mp.register_script_message("rpc-reply", function(id, result)
+//!     local callback = pending[id]
+//!     pending[id] = nil
+//!     if callback then callback(utils.parse_json(result)) end
+//! end)
+//!
+//! function rpc_call(target, method, args, callback)
+//!     next_id = next_id + 1
+//!     local id = tostring(next_id)
+//!     pending[id] = callback
+//!     mp.commandv("script-message-to", target, "rpc-request", id, mp.get_script_name(), method, utils.format_json(args))
+//! end
+//! ```
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::*;
+use crate::error::RustError;
+use crate::event::ClientMessage;
+use crate::types::json;
+
+/// The client message name of a request.
+pub const REQUEST: &str = "rpc-request";
+/// The client message name of a successful reply.
+pub const REPLY: &str = "rpc-reply";
+/// The client message name of a failed reply.
+pub const ERROR: &str = "rpc-error";
+
+/// The default timeout of [`Rpc::call()`].
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Handler<'a> = Box<dyn FnMut(Node) -> Result<Node> + 'a>;
+
+/// A request/response layer over [`script-message-to`](https://mpv.io/manual/stable/#command-interface-script-message-to[-]]]).
+///
+/// [`Rpc`] runs on its own weak [`Client`], so its messages never mix with the events of the [`Handle`] it was created from.
+/// Other clients can address it either by the name given to [`Rpc::new()`] or by [`Rpc::address()`].
+///
+/// Incoming requests are only handled while [`Rpc::poll()`], [`Rpc::run()`] or [`Rpc::call()`] is running.
+/// See the [module documentation](crate::rpc) for the wire protocol.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::rpc::Rpc;
+///#
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// let mut rpc = Rpc::new(&handle, "my-plugin-rpc")?;
+///
+/// rpc.serve("pause", |_args| {
+///     handle.set_property("pause", true)?;
+///     Ok(Node::None)
+/// });
+///
+/// let sum = rpc.call("my-lua-script", "add", node_map! { ("a", 1), ("b", 2) })?;
+/// assert_eq!(sum, Node::Int64(3));
+///
+/// // Serve requests until mpv shuts down.
+/// rpc.run()?;
+///#     Ok(())
+///# }
+/// ```
+pub struct Rpc<'a> {
+    client: Client,
+    handlers: HashMap<String, Handler<'a>>,
+    timeout: Duration,
+    next_id: u64,
+    shutdown: bool,
+}
+
+impl<'a> Rpc<'a> {
+    /// Create a new weak [`Client`] named `name` on the same core as `handle`, and use it for RPC.
+    pub fn new(handle: &Handle, name: &str) -> Result<Self> {
+        Ok(Self {
+            client: handle.create_weak_client(name)?,
+            handlers: HashMap::new(),
+            timeout: DEFAULT_TIMEOUT,
+            next_id: 1,
+            shutdown: false,
+        })
+    }
+
+    /// The [`Client`] used for RPC.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// The address of this [`Rpc`] in the form `@<client_id>`, which is sent as the reply target of every request.
+    pub fn address(&self) -> String {
+        format!("@{}", self.client.client_id())
+    }
+
+    /// Set the timeout used by [`Rpc::call()`]. The default is [`DEFAULT_TIMEOUT`].
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Register `handler` to answer requests for `method`, replacing any previous handler for it.
+    ///
+    /// The handler receives the decoded arguments. Its [`Ok`] value is sent back as the result,
+    /// while an [`Err`] is sent back as an `rpc-error` with the error's [`Debug`] representation.
+    pub fn serve(&mut self, method: &str, handler: impl FnMut(Node) -> Result<Node> + 'a) {
+        self.handlers.insert(method.to_string(), Box::new(handler));
+    }
+
+    /// Remove the handler for `method`. Returns `false` if there was none.
+    pub fn unserve(&mut self, method: &str) -> bool {
+        self.handlers.remove(method).is_some()
+    }
+
+    /// Call `method` on the client `target` with `args`, and wait for its result.
+    ///
+    /// `target` is a client name or an `@<client_id>` address, as accepted by `script-message-to`.
+    ///
+    /// Requests sent to this [`Rpc`] are still served while waiting, so the target may call back into us.
+    ///
+    /// # Errors
+    /// - [`RustError::Rpc`] with the remote message if the target answered with an error.
+    /// - [`RustError::Timeout`] if no answer arrived within the timeout (see [`Rpc::set_timeout()`]).
+    /// - [`RustError::Shutdown`] if mpv is shutting down.
+    /// - [`RustError::Json`] if the arguments could not be encoded or the result could not be decoded.
+    pub fn call(&mut self, target: &str, method: &str, args: Node) -> Result<Node> {
+        if self.shutdown {
+            return Err(Error::Rust(RustError::Shutdown));
+        }
+
+        let id = self.next_id.to_string();
+        self.next_id += 1;
+
+        let args = json::write(&args)?;
+        self.client.command(&["script-message-to", target, REQUEST, &id, &self.address(), method, &args])?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Error::Rust(RustError::Timeout));
+            }
+
+            match self.client.wait_event(remaining.as_secs_f64())? {
+                Event::Shutdown => {
+                    self.shutdown = true;
+                    return Err(Error::Rust(RustError::Shutdown));
+                }
+                Event::ClientMessage(message) => {
+                    if let Some((reply_id, result)) = self.handle_message(&message) && reply_id == id {
+                        return result;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Wait up to `timeout` seconds for one event and serve it if it is a request.
+    ///
+    /// `timeout` has the same meaning as in [`Handle::wait_event()`].
+    /// Returns `false` once mpv is shutting down, after which the [`Rpc`] should be dropped.
+    pub fn poll(&mut self, timeout: f64) -> Result<bool> {
+        if self.shutdown {
+            return Ok(false);
+        }
+
+        match self.client.wait_event(timeout)? {
+            Event::Shutdown => self.shutdown = true,
+            Event::ClientMessage(message) => { self.handle_message(&message); }
+            _ => {}
+        }
+
+        Ok(!self.shutdown)
+    }
+
+    /// Serve requests until mpv shuts down.
+    pub fn run(&mut self) -> Result<()> {
+        while self.poll(-1.0)? {}
+        Ok(())
+    }
+
+    /// Serve a request, or decode a reply into its correlation id and result. Other messages are ignored.
+    fn handle_message(&mut self, message: &ClientMessage) -> Option<(String, Result<Node>)> {
+        match message.0.as_slice() {
+            [name, id, reply_to, method, args] if name == REQUEST => {
+                let result = match self.handlers.get_mut(method.as_str()) {
+                    Some(handler) => json::parse(args).and_then(handler).and_then(|result| json::write(&result)),
+                    None => Err(Error::Rust(RustError::Rpc(format!("unknown method: {method}")))),
+                };
+
+                let sent = match result {
+                    Ok(result) => self.client.command(&["script-message-to", reply_to, REPLY, id, &result]),
+                    Err(Error::Rust(RustError::Rpc(message))) => self.client.command(&["script-message-to", reply_to, ERROR, id, &message]),
+                    Err(e) => self.client.command(&["script-message-to", reply_to, ERROR, id, &format!("{e:?}")]),
+                };
+
+                // The caller may be gone already, which must not stop us from serving others.
+                if let Err(e) = sent {
+                    logging::report_error(&self.client, &format!("failed to reply to {reply_to}"), &e);
+                }
+
+                None
+            }
+            [name, id, result] if name == REPLY => Some((id.clone(), json::parse(result))),
+            [name, id, message] if name == ERROR => Some((id.clone(), Err(Error::Rust(RustError::Rpc(message.clone()))))),
+            _ => None,
+        }
+    }
+}
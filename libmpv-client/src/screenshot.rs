@@ -0,0 +1,253 @@
+//! Frame grabbing with mpv's [`screenshot-raw`](https://mpv.io/manual/stable/#command-interface-screenshot-raw) command.
+//!
+//! [`Image`] decodes the command's result into owned pixels, converts them into packed RGBA,
+//! and can encode them as PNG or PPM without any extra dependencies.
+
+use crate::*;
+use crate::types::node_map::take;
+
+/// Which parts of the output are included in a screenshot.
+///
+/// See the flags of [`screenshot-raw`](https://mpv.io/manual/stable/#command-interface-screenshot-raw).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScreenshotMode {
+    /// The video with subtitles, but without the OSD. This is mpv's default.
+    #[default]
+    Subtitles,
+    /// The video only, without subtitles or OSD.
+    Video,
+    /// The contents of the window, including the OSD. The image has the window's size rather than the video's.
+    Window,
+}
+
+impl ScreenshotMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ScreenshotMode::Subtitles => "subtitles",
+            ScreenshotMode::Video => "video",
+            ScreenshotMode::Window => "window",
+        }
+    }
+}
+
+/// The pixel layout of an [`Image`], as reported in the `format` field of [`screenshot-raw`](https://mpv.io/manual/stable/#command-interface-screenshot-raw).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 8 bits per component in `B, G, R, X` order, where `X` is padding.
+    Bgr0,
+    /// 8 bits per component in `B, G, R, A` order.
+    Bgra,
+    /// 8 bits per component in `R, G, B, A` order.
+    Rgba,
+    /// 16 bits per component in native endianness, in `R, G, B, A` order.
+    Rgba64,
+    /// Any other format, by name.
+    Other(String),
+}
+
+impl PixelFormat {
+    /// The number of bytes per pixel, or [`None`] for [`PixelFormat::Other`].
+    pub fn bytes_per_pixel(&self) -> Option<usize> {
+        match self {
+            PixelFormat::Bgr0 | PixelFormat::Bgra | PixelFormat::Rgba => Some(4),
+            PixelFormat::Rgba64 => Some(8),
+            PixelFormat::Other(_) => None,
+        }
+    }
+}
+
+impl From<&str> for PixelFormat {
+    fn from(value: &str) -> Self {
+        match value {
+            "bgr0" => PixelFormat::Bgr0,
+            "bgra" => PixelFormat::Bgra,
+            "rgba" => PixelFormat::Rgba,
+            "rgba64" => PixelFormat::Rgba64,
+            other => PixelFormat::Other(other.to_string()),
+        }
+    }
+}
+
+/// An owned frame returned by [`screenshot-raw`](https://mpv.io/manual/stable/#command-interface-screenshot-raw).
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::screenshot::{Image, ScreenshotMode};
+///#
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// let frame = Image::capture(&handle, ScreenshotMode::Video)?;
+/// std::fs::write("frame.png", frame.to_png()?).unwrap();
+///#     Ok(())
+///# }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// The number of bytes between the starts of two rows in [`Image::pixels`], which may include padding.
+    pub stride: usize,
+    /// The layout of [`Image::pixels`].
+    pub format: PixelFormat,
+    /// The raw pixel data, row by row.
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Take a screenshot of the current frame with `screenshot-raw`.
+    pub fn capture(handle: &Handle, mode: ScreenshotMode) -> Result<Self> {
+        Self::from_node(handle.command_ret(&["screenshot-raw", mode.as_str()])?)
+    }
+
+    /// Decode the [`Node::Map`] returned by `screenshot-raw`.
+    ///
+    /// Returns [`Error::InvalidParameter`] if a field is missing, has the wrong type, or `data` is too short for the given size and stride.
+    pub fn from_node(node: Node) -> Result<Self> {
+        let Node::Map(mut map) = node else {
+            return Err(Error::InvalidParameter);
+        };
+
+        let (Some(Node::Int64(w)), Some(Node::Int64(h)), Some(Node::Int64(stride)), Some(Node::String(format)), Some(Node::ByteArray(data))) =
+            (take(&mut map, "w"), take(&mut map, "h"), take(&mut map, "stride"), take(&mut map, "format"), take(&mut map, "data")) else {
+            return Err(Error::InvalidParameter);
+        };
+
+        let (Ok(width), Ok(height), Ok(stride)) = (u32::try_from(w), u32::try_from(h), usize::try_from(stride)) else {
+            return Err(Error::InvalidParameter);
+        };
+
+        let image = Self { width, height, stride, format: PixelFormat::from(format.as_str()), pixels: data };
+
+        if let Some(bpp) = image.format.bytes_per_pixel() && height > 0
+            && (stride < width as usize * bpp || image.pixels.len() < stride * (height as usize - 1) + width as usize * bpp) {
+            return Err(Error::InvalidParameter);
+        }
+
+        Ok(image)
+    }
+
+    /// Iterate over the rows of the image, without padding.
+    fn rows(&self, bpp: usize) -> impl Iterator<Item = &[u8]> {
+        let row_len = self.width as usize * bpp;
+        (0..self.height as usize).map(move |y| &self.pixels[y * self.stride..y * self.stride + row_len])
+    }
+
+    /// Convert the image into packed, straight-alpha `[R, G, B, A]` pixels, row by row, without padding.
+    ///
+    /// [`PixelFormat::Bgr0`] becomes fully opaque, and [`PixelFormat::Rgba64`] is reduced to 8 bits per component.
+    /// Returns [`Error::Unsupported`] for [`PixelFormat::Other`].
+    pub fn to_rgba(&self) -> Result<Vec<u8>> {
+        let bpp = self.format.bytes_per_pixel().ok_or(Error::Unsupported)?;
+        let mut rgba = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+
+        for row in self.rows(bpp) {
+            for px in row.chunks_exact(bpp) {
+                match self.format {
+                    PixelFormat::Bgr0 => rgba.extend_from_slice(&[px[2], px[1], px[0], 255]),
+                    PixelFormat::Bgra => rgba.extend_from_slice(&[px[2], px[1], px[0], px[3]]),
+                    PixelFormat::Rgba => rgba.extend_from_slice(px),
+                    PixelFormat::Rgba64 => rgba.extend(px.chunks_exact(2).map(|c| (u16::from_ne_bytes([c[0], c[1]]) >> 8) as u8)),
+                    PixelFormat::Other(_) => unreachable!(),
+                }
+            }
+        }
+
+        Ok(rgba)
+    }
+
+    /// Encode the image as an 8-bit RGBA PNG.
+    ///
+    /// The image data is stored uncompressed, which is fast but produces large files.
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        let rgba = self.to_rgba()?;
+
+        // Every row starts with filter type 0 (none).
+        let row_len = self.width as usize * 4;
+        let mut raw = Vec::with_capacity((row_len + 1) * self.height as usize);
+        for row in rgba.chunks_exact(row_len.max(1)).take(self.height as usize) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&self.width.to_be_bytes());
+        ihdr.extend_from_slice(&self.height.to_be_bytes());
+        ihdr.extend_from_slice(&[8, 6, 0, 0, 0]); // 8 bits, RGBA, deflate, no filtering, no interlacing
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut png, b"IHDR", &ihdr);
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut png, b"IEND", &[]);
+        Ok(png)
+    }
+
+    /// Encode the image as a binary PPM (`P6`). The alpha channel is dropped.
+    pub fn to_ppm(&self) -> Result<Vec<u8>> {
+        let rgba = self.to_rgba()?;
+
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.reserve(self.width as usize * self.height as usize * 3);
+        for px in rgba.chunks_exact(4) {
+            ppm.extend_from_slice(&px[..3]);
+        }
+        Ok(ppm)
+    }
+}
+
+/// Append a PNG chunk with its length and CRC.
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc32(&[kind, data]).to_be_bytes());
+}
+
+/// Wrap `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xFFFF;
+
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        out.push(blocks.peek().is_none() as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,313 @@
+//! An aggregated [`PlayerState`], kept up to date from observed properties and events by a [`PlayerStateTracker`].
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use crate::*;
+use crate::dispatch::Dispatcher;
+use crate::event::{EndFileReason, PropertyValue};
+use crate::logging::report_error;
+use crate::observer::{decode, next_userdata};
+
+/// A snapshot of the state of the player, as shown by a typical UI.
+///
+/// Values are [`None`] while the underlying property is unavailable, such as `duration` when no file is loaded.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct PlayerState {
+    /// The `path` of the current file.
+    pub path: Option<String>,
+    /// The `media-title` of the current file.
+    pub title: Option<String>,
+    /// Whether playback is paused (`pause`).
+    pub paused: bool,
+    /// Whether the player is idle, with no file loaded (`idle-active`).
+    pub idle: bool,
+    /// The current position in seconds (`time-pos`).
+    pub time_pos: Option<f64>,
+    /// The duration of the current file in seconds (`duration`).
+    pub duration: Option<f64>,
+    /// The volume in percent (`volume`).
+    pub volume: Option<f64>,
+    /// Whether audio is muted (`mute`).
+    pub mute: bool,
+    /// The selected tracks.
+    pub tracks: SelectedTracks,
+    /// The index of the current entry in the playlist (`playlist-pos`), or [`None`] if there is none.
+    pub playlist_pos: Option<i64>,
+    /// Whether playback is paused to wait for the cache (`paused-for-cache`).
+    pub paused_for_cache: bool,
+    /// How full the cache is in percent while buffering (`cache-buffering-state`).
+    pub cache_buffering: Option<i64>,
+    /// The duration of the cached data ahead of the current position in seconds (`demuxer-cache-duration`).
+    pub cache_duration: Option<f64>,
+    /// The reason the last file ended, from the last [`Event::EndFile`].
+    pub last_end_file: Option<EndFileReason>,
+}
+
+/// The IDs of the selected tracks, or [`None`] if no track of the kind is selected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SelectedTracks {
+    /// The selected video track (`vid`).
+    pub video: Option<i64>,
+    /// The selected audio track (`aid`).
+    pub audio: Option<i64>,
+    /// The selected subtitle track (`sid`).
+    pub sub: Option<i64>,
+}
+
+type ChangeListener<'a> = Box<dyn FnMut(&PlayerState, &PlayerState) + 'a>;
+
+/// The observed properties, with the format each is read in.
+const PROPERTIES: [(&str, Format); 15] = [
+    ("path", Format::STRING),
+    ("media-title", Format::STRING),
+    ("pause", Format::FLAG),
+    ("idle-active", Format::FLAG),
+    ("time-pos", Format::DOUBLE),
+    ("duration", Format::DOUBLE),
+    ("volume", Format::DOUBLE),
+    ("mute", Format::FLAG),
+    ("vid", Format::NODE),
+    ("aid", Format::NODE),
+    ("sid", Format::NODE),
+    ("playlist-pos", Format::INT64),
+    ("paused-for-cache", Format::FLAG),
+    ("cache-buffering-state", Format::INT64),
+    ("demuxer-cache-duration", Format::DOUBLE),
+];
+
+/// Where to read the new value of a property from.
+#[derive(Clone, Copy)]
+enum Source<'s> {
+    /// The value of an [`Event::PropertyChange`].
+    Change(&'s PropertyValue),
+    /// Read the property with [`Handle::get_property()`].
+    Fetch(&'s Handle),
+}
+
+/// Read the property `name` as a `T` from `source`, or [`None`] if it is unavailable.
+fn read<T: MpvRecv>(source: Source<'_>, name: &str) -> Result<Option<T>> {
+    match source {
+        Source::Change(value) => decode(value),
+        Source::Fetch(handle) => match handle.get_property::<T>(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(Error::PropertyUnavailable | Error::PropertyNotFound) => Ok(None),
+            Err(e) => Err(e),
+        },
+    }
+}
+
+/// Store the value of the property `name` from `source` in `state`, returning whether it is one of [`PROPERTIES`].
+fn apply(state: &mut PlayerState, name: &str, source: Source<'_>) -> Result<bool> {
+    match name {
+        "path" => state.path = read(source, name)?,
+        "media-title" => state.title = read(source, name)?,
+        "pause" => state.paused = read(source, name)?.unwrap_or(false),
+        "idle-active" => state.idle = read(source, name)?.unwrap_or(false),
+        "time-pos" => state.time_pos = read(source, name)?,
+        "duration" => state.duration = read(source, name)?,
+        "volume" => state.volume = read(source, name)?,
+        "mute" => state.mute = read(source, name)?.unwrap_or(false),
+        "vid" => state.tracks.video = track_id(read(source, name)?),
+        "aid" => state.tracks.audio = track_id(read(source, name)?),
+        "sid" => state.tracks.sub = track_id(read(source, name)?),
+        "playlist-pos" => state.playlist_pos = read::<i64>(source, name)?.filter(|&pos| pos >= 0),
+        "paused-for-cache" => state.paused_for_cache = read(source, name)?.unwrap_or(false),
+        "cache-buffering-state" => state.cache_buffering = read(source, name)?,
+        "demuxer-cache-duration" => state.cache_duration = read(source, name)?,
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// A track property such as `aid` is either a track ID, or `no`/`auto` when none is selected.
+pub(crate) fn track_id(node: Option<Node>) -> Option<i64> {
+    match node {
+        Some(Node::Int64(id)) => Some(id),
+        _ => None,
+    }
+}
+
+struct Inner<'a> {
+    state: Arc<PlayerState>,
+    listeners: Vec<ChangeListener<'a>>,
+}
+
+/// Maintains a [`PlayerState`] from the events received on a [`Handle`].
+///
+/// The properties are observed with a single `userdata` value, by [`PlayerStateTracker::observe()`] for a hand-written event loop,
+/// or by [`PlayerStateTracker::install()`] for a [`Dispatcher`], which then keeps the state up to date by itself.
+/// In a hand-written loop, every event received from [`Handle::wait_event()`] should be passed to [`PlayerStateTracker::update()`],
+/// and [`PlayerStateTracker::resync()`] should be called after an [`Event::QueueOverflow`], since changes may have been dropped.
+///
+/// The tracker can be cloned, and all clones share the same state, so one can be kept while another is installed.
+/// [`PlayerStateTracker::snapshot()`] is cheap, and the snapshot never changes, so it can be handed to other threads.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::dispatch::Dispatcher;
+///# use libmpv_client::state::PlayerStateTracker;
+///#
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+/// let mut dispatcher = Dispatcher::new(Handle::from_ptr(ptr));
+///
+/// let tracker = PlayerStateTracker::new();
+/// tracker.on_change(|previous, current| {
+///     if previous.paused != current.paused {
+///         println!("paused: {}", current.paused);
+///     }
+/// });
+/// tracker.install(&mut dispatcher)?;
+///
+/// dispatcher.run()?;
+/// println!("last position: {:?}", tracker.snapshot().time_pos);
+///#     Ok(())
+///# }
+/// ```
+#[derive(Clone)]
+pub struct PlayerStateTracker<'a> {
+    userdata: u64,
+    inner: Rc<RefCell<Inner<'a>>>,
+}
+
+impl<'a> PlayerStateTracker<'a> {
+    /// Create a tracker with an empty state. Nothing is observed until [`PlayerStateTracker::observe()`] or [`PlayerStateTracker::install()`] is called.
+    pub fn new() -> Self {
+        Self {
+            userdata: next_userdata(),
+            inner: Rc::new(RefCell::new(Inner { state: Arc::default(), listeners: Vec::new() })),
+        }
+    }
+
+    /// The `userdata` value of the [`Event::PropertyChange`] events of the observed properties.
+    pub fn userdata(&self) -> u64 {
+        self.userdata
+    }
+
+    /// Observe the properties of a [`PlayerState`] on `handle`.
+    ///
+    /// mpv sends the initial value of every observed property as an [`Event::PropertyChange`],
+    /// so the state is filled in as soon as those events are passed to [`PlayerStateTracker::update()`].
+    pub fn observe(&self, handle: &Handle) -> Result<()> {
+        for (name, format) in PROPERTIES {
+            handle.observe_property(name, format, self.userdata)?;
+        }
+        Ok(())
+    }
+
+    /// Stop observing the properties on `handle`.
+    pub fn unobserve(&self, handle: &Handle) -> Result<()> {
+        handle.unobserve_property(self.userdata)?;
+        Ok(())
+    }
+
+    /// Observe the properties on the [`Handle`] of `dispatcher`, and register handlers which keep the state up to date.
+    ///
+    /// After an [`Event::QueueOverflow`], all properties are read again with [`PlayerStateTracker::resync()`].
+    ///
+    /// Errors while updating the state, such as a value of an unexpected format, don't stop the [`Dispatcher`].
+    /// The state is left as it was, and the error is logged at error level with the `log` or `tracing` feature, or printed through mpv otherwise.
+    pub fn install(&self, dispatcher: &mut Dispatcher<'a>) -> Result<()> {
+        self.observe(dispatcher.handle())?;
+
+        for event_id in [EventId::PROPERTY_CHANGE, EventId::END_FILE] {
+            let tracker = self.clone();
+            dispatcher.on_event(event_id, move |handle, event| {
+                tracker.handle_event(event, |context, error| report_error(handle, context, error));
+                Ok(())
+            })?;
+        }
+
+        let tracker = self.clone();
+        dispatcher.on_event(EventId::QUEUE_OVERFLOW, move |handle, _event| {
+            if let Err(e) = tracker.resync(handle) {
+                report_error(handle, "failed to resync the player state", &e);
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Update the state from `event` in a handler installed by [`PlayerStateTracker::install()`], passing any error to `report`.
+    pub(crate) fn handle_event(&self, event: &Event, report: impl FnOnce(&str, &Error)) {
+        if let Err(e) = self.update(event) {
+            let context = match event {
+                Event::PropertyChange(change) => format!("failed to update the player state from {}", change.name),
+                _ => "failed to update the player state".to_string(),
+            };
+            report(&context, &e);
+        }
+    }
+
+    /// The current state. This only clones an [`Arc`].
+    pub fn snapshot(&self) -> Arc<PlayerState> {
+        Arc::clone(&self.inner.borrow().state)
+    }
+
+    /// Call `listener` with the previous and the current state every time the state changes.
+    pub fn on_change(&self, listener: impl FnMut(&PlayerState, &PlayerState) + 'a) {
+        self.inner.borrow_mut().listeners.push(Box::new(listener));
+    }
+
+    /// Update the state from `event`, returning whether it changed.
+    ///
+    /// Events which don't affect the state, including changes of properties observed elsewhere, are ignored.
+    pub fn update(&self, event: &Event) -> Result<bool> {
+        let mut next = PlayerState::clone(&self.snapshot());
+
+        match event {
+            Event::PropertyChange(change) if change.userdata == self.userdata => {
+                let value = change.value.as_ref().map_err(Error::clone)?;
+                if !apply(&mut next, &change.name, Source::Change(value))? {
+                    return Ok(false);
+                }
+            }
+            Event::EndFile(end_file) => next.last_end_file = Some(end_file.reason.clone()),
+            _ => return Ok(false),
+        }
+
+        Ok(self.publish(next))
+    }
+
+    /// Read every property again with [`Handle::get_property()`].
+    pub fn resync(&self, handle: &Handle) -> Result<()> {
+        let mut next = PlayerState::clone(&self.snapshot());
+        for (name, _) in PROPERTIES {
+            apply(&mut next, name, Source::Fetch(handle))?;
+        }
+        self.publish(next);
+        Ok(())
+    }
+
+    /// Replace the state with `next` and notify the listeners, returning whether it differed from the current state.
+    fn publish(&self, next: PlayerState) -> bool {
+        let (previous, current, mut listeners) = {
+            let mut inner = self.inner.borrow_mut();
+            if *inner.state == next {
+                return false;
+            }
+
+            let previous = std::mem::replace(&mut inner.state, Arc::new(next));
+            (previous, Arc::clone(&inner.state), std::mem::take(&mut inner.listeners))
+        };
+
+        // The state isn't borrowed while the listeners run, so they can use the tracker, and even add listeners.
+        for listener in &mut listeners {
+            listener(&previous, &current);
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        listeners.append(&mut inner.listeners);
+        inner.listeners = listeners;
+        true
+    }
+}
+
+impl Default for PlayerStateTracker<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,268 @@
+//! A typed wrapper around mpv's [`subprocess`](https://mpv.io/manual/stable/#command-interface-subprocess) command.
+//!
+//! Running processes through mpv rather than [`std::process`] ties them to mpv's lifecycle:
+//! by default, they are killed when playback stops, and asynchronous runs can be cancelled with [`Handle::abort_async_command()`].
+
+use crate::*;
+use crate::event::CommandReply;
+
+/// A builder for the [`subprocess`](https://mpv.io/manual/stable/#command-interface-subprocess) command.
+///
+/// The defaults match mpv's: output is not captured, and the process is killed when playback stops.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///# use libmpv_client::subprocess::Subprocess;
+///#
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// let output = Subprocess::new("yt-dlp")
+///     .args(["--get-title", "https://example.com/video"])
+///     .capture_stdout(true)
+///     .run(&handle)?;
+///
+/// if output.success() {
+///     println!("title: {}", output.stdout_str()?.trim());
+/// }
+///#     Ok(())
+///# }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Subprocess {
+    args: Vec<String>,
+    env: Option<Vec<String>>,
+    stdin_data: Option<String>,
+    passthrough_stdin: bool,
+    capture_stdout: bool,
+    capture_stderr: bool,
+    capture_size: Option<i64>,
+    playback_only: bool,
+    detach: bool,
+}
+
+impl Subprocess {
+    /// Create a command running `program`, which is looked up in `PATH` if it isn't a path.
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            args: vec![program.into()],
+            env: None,
+            stdin_data: None,
+            passthrough_stdin: false,
+            capture_stdout: false,
+            capture_stderr: false,
+            capture_size: None,
+            playback_only: true,
+            detach: false,
+        }
+    }
+
+    /// Append an argument.
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Append several arguments.
+    pub fn args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add an environment variable.
+    ///
+    /// Once any variable is added, the process gets _only_ the variables added this way, instead of inheriting mpv's environment.
+    pub fn env(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.env.get_or_insert_with(Vec::new).push(format!("{}={}", key.as_ref(), value.as_ref()));
+        self
+    }
+
+    /// Feed `data` to the process' stdin.
+    pub fn stdin_data(mut self, data: impl Into<String>) -> Self {
+        self.stdin_data = Some(data.into());
+        self
+    }
+
+    /// Let the process read mpv's own stdin. Ignored if [`Subprocess::stdin_data()`] is set.
+    pub fn passthrough_stdin(mut self, enable: bool) -> Self {
+        self.passthrough_stdin = enable;
+        self
+    }
+
+    /// Capture the process' stdout into [`SubprocessResult::stdout`] instead of passing it through to mpv's stdout.
+    pub fn capture_stdout(mut self, enable: bool) -> Self {
+        self.capture_stdout = enable;
+        self
+    }
+
+    /// Capture the process' stderr into [`SubprocessResult::stderr`] instead of passing it through to mpv's stderr.
+    pub fn capture_stderr(mut self, enable: bool) -> Self {
+        self.capture_stderr = enable;
+        self
+    }
+
+    /// Limit the captured output to `bytes` bytes per stream. Output beyond this is discarded. mpv's default is 64 MiB.
+    pub fn capture_size(mut self, bytes: i64) -> Self {
+        self.capture_size = Some(bytes);
+        self
+    }
+
+    /// Whether the process is killed when playback stops (the default), or keeps running while mpv is idle.
+    pub fn playback_only(mut self, enable: bool) -> Self {
+        self.playback_only = enable;
+        self
+    }
+
+    /// Detach the process from mpv, so that it keeps running after mpv exits.
+    ///
+    /// The command then returns as soon as the process is started, and output can't be captured.
+    pub fn detach(mut self, enable: bool) -> Self {
+        self.detach = enable;
+        self
+    }
+
+    /// Build the named-argument [`Node`] for [`Handle::command_node()`].
+    pub fn to_node(&self) -> Node {
+        let mut map = NodeMap::from([
+            ("name".to_string(), Node::from("subprocess")),
+            ("args".to_string(), Node::Array(self.args.iter().map(|arg| Node::String(arg.clone())).collect())),
+            ("playback_only".to_string(), Node::Flag(self.playback_only)),
+            ("capture_stdout".to_string(), Node::Flag(self.capture_stdout)),
+            ("capture_stderr".to_string(), Node::Flag(self.capture_stderr)),
+            ("detach".to_string(), Node::Flag(self.detach)),
+            ("passthrough_stdin".to_string(), Node::Flag(self.passthrough_stdin)),
+        ]);
+
+        if let Some(env) = &self.env {
+            map.insert("env".to_string(), Node::Array(env.iter().map(|var| Node::String(var.clone())).collect()));
+        }
+        if let Some(data) = &self.stdin_data {
+            map.insert("stdin_data".to_string(), Node::String(data.clone()));
+        }
+        if let Some(size) = self.capture_size {
+            map.insert("capture_size".to_string(), Node::Int64(size));
+        }
+
+        Node::Map(map)
+    }
+
+    /// Run the process and block until it exits.
+    ///
+    /// Note that this blocks the calling thread, but not mpv. For long-running processes, prefer [`Subprocess::spawn()`].
+    pub fn run(&self, handle: &Handle) -> Result<SubprocessResult> {
+        SubprocessResult::from_node(handle.command_node(self.to_node())?)
+    }
+
+    /// Start the process without waiting for it.
+    ///
+    /// Once it exits, `handle` receives an [`Event::CommandReply`] with [`userdata`](field@CommandReply::userdata) set to `userdata`,
+    /// which can be decoded with [`SubprocessResult::from_reply()`].
+    /// The process can be killed early with [`PendingSubprocess::cancel()`].
+    ///
+    /// # Example
+    /// ```
+    ///# use libmpv_client::*;
+    ///# use libmpv_client::subprocess::{Subprocess, SubprocessResult};
+    ///#
+    ///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+    ///#     let handle = Handle::from_ptr(ptr);
+    /// let pending = Subprocess::new("sleep").arg("60").spawn(&handle, 7)?;
+    /// pending.cancel();
+    ///
+    /// loop {
+    ///     if let Event::CommandReply(reply) = handle.wait_event(-1.0)? && reply.userdata == pending.userdata() {
+    ///         let result = SubprocessResult::from_reply(reply)?;
+    ///         assert!(result.killed_by_us);
+    ///         break;
+    ///     }
+    /// }
+    ///#     Ok(())
+    ///# }
+    /// ```
+    pub fn spawn<'a>(&self, handle: &'a Handle, userdata: u64) -> Result<PendingSubprocess<'a>> {
+        handle.command_node_async(userdata, self.to_node())?;
+        Ok(PendingSubprocess { handle, userdata })
+    }
+}
+
+/// A process started with [`Subprocess::spawn()`].
+///
+/// Dropping this does not affect the process.
+pub struct PendingSubprocess<'a> {
+    handle: &'a Handle,
+    userdata: u64,
+}
+
+impl PendingSubprocess<'_> {
+    /// The `userdata` of the [`Event::CommandReply`] which will be sent once the process exits.
+    pub fn userdata(&self) -> u64 {
+        self.userdata
+    }
+
+    /// Kill the process. Its [`Event::CommandReply`] is still sent, with [`SubprocessResult::killed_by_us`] set.
+    pub fn cancel(&self) {
+        self.handle.abort_async_command(self.userdata);
+    }
+}
+
+/// The result of a [`subprocess`](https://mpv.io/manual/stable/#command-interface-subprocess) command.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubprocessResult {
+    /// The exit code of the process, or a negative value if it was killed by a signal or could not be started.
+    pub status: i64,
+    /// The captured stdout, if [`Subprocess::capture_stdout()`] was enabled.
+    pub stdout: Vec<u8>,
+    /// The captured stderr, if [`Subprocess::capture_stderr()`] was enabled.
+    pub stderr: Vec<u8>,
+    /// Empty on success, otherwise a description of the failure, such as `killed` or `init`.
+    pub error_string: String,
+    /// Whether the process was killed by mpv, for example by [`PendingSubprocess::cancel()`] or because playback stopped.
+    pub killed_by_us: bool,
+}
+
+impl SubprocessResult {
+    /// Decode the [`Node::Map`] returned by the command. Missing fields keep their [`Default`] values.
+    ///
+    /// Returns [`Error::Command`] if `node` is not a [`Node::Map`].
+    pub fn from_node(node: Node) -> Result<Self> {
+        let Node::Map(map) = node else {
+            return Err(Error::Command);
+        };
+
+        let mut result = Self::default();
+        for (key, value) in map {
+            match (key.as_str(), value) {
+                ("status", Node::Int64(status)) => result.status = status,
+                ("stdout", Node::ByteArray(bytes)) => result.stdout = bytes,
+                ("stdout", Node::String(s)) => result.stdout = s.into_bytes(),
+                ("stderr", Node::ByteArray(bytes)) => result.stderr = bytes,
+                ("stderr", Node::String(s)) => result.stderr = s.into_bytes(),
+                ("error_string", Node::String(s)) => result.error_string = s,
+                ("killed_by_us", Node::Flag(killed)) => result.killed_by_us = killed,
+                _ => {}
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Decode the [`Event::CommandReply`] of a process started with [`Subprocess::spawn()`].
+    pub fn from_reply(reply: CommandReply) -> Result<Self> {
+        Self::from_node(reply.result?)
+    }
+
+    /// Whether the process ran and exited with status `0`.
+    pub fn success(&self) -> bool {
+        self.status == 0 && self.error_string.is_empty()
+    }
+
+    /// The captured stdout as UTF-8.
+    pub fn stdout_str(&self) -> Result<&str> {
+        Ok(std::str::from_utf8(&self.stdout)?)
+    }
+
+    /// The captured stderr as UTF-8.
+    pub fn stderr_str(&self) -> Result<&str> {
+        Ok(std::str::from_utf8(&self.stderr)?)
+    }
+}
@@ -1,12 +1,59 @@
 #![cfg(test)]
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::ffi::c_void;
 use libmpv_client_sys::mpv_node;
 
 thread_local! {
     pub(crate) static MPV_FREE_CALLS: Cell<usize> = Cell::new(0);
     pub(crate) static MPV_FREE_NODE_CONTENTS_CALLS: Cell<usize> = Cell::new(0);
+    /// The calls made to the functions in [`mpv`], such as `hook_continue 42`.
+    pub(crate) static MPV_CALLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Stand-ins for the libmpv functions used by [`Handle`](crate::Handle), which record their calls in [`MPV_CALLS`].
+///
+/// Any other function is the real one, which can't be called without mpv.
+pub(crate) mod mpv {
+    use std::ffi::{CStr, c_char, c_int, c_void};
+    pub(crate) use libmpv_client_sys::*;
+    use super::MPV_CALLS;
+
+    fn record(call: String) {
+        MPV_CALLS.with_borrow_mut(|calls| calls.push(call));
+    }
+
+    pub(crate) unsafe fn destroy(_ctx: *mut mpv_handle) {}
+
+    pub(crate) unsafe fn get_property(_ctx: *mut mpv_handle, name: *const c_char, _format: mpv_format, _data: *mut c_void) -> c_int {
+        record(format!("get_property {}", unsafe { CStr::from_ptr(name) }.to_string_lossy()));
+        mpv_error_MPV_ERROR_PROPERTY_UNAVAILABLE
+    }
+
+    pub(crate) unsafe fn observe_property(_ctx: *mut mpv_handle, reply_userdata: u64, name: *const c_char, _format: mpv_format) -> c_int {
+        record(format!("observe_property {} {reply_userdata}", unsafe { CStr::from_ptr(name) }.to_string_lossy()));
+        0
+    }
+
+    pub(crate) unsafe fn unobserve_property(_ctx: *mut mpv_handle, registered_reply_userdata: u64) -> c_int {
+        record(format!("unobserve_property {registered_reply_userdata}"));
+        1
+    }
+
+    pub(crate) unsafe fn request_event(_ctx: *mut mpv_handle, event: mpv_event_id, enable: c_int) -> c_int {
+        record(format!("request_event {event} {enable}"));
+        0
+    }
+
+    pub(crate) unsafe fn hook_add(_ctx: *mut mpv_handle, reply_userdata: u64, name: *const c_char, priority: c_int) -> c_int {
+        record(format!("hook_add {} {reply_userdata} {priority}", unsafe { CStr::from_ptr(name) }.to_string_lossy()));
+        0
+    }
+
+    pub(crate) unsafe fn hook_continue(_ctx: *mut mpv_handle, id: u64) -> c_int {
+        record(format!("hook_continue {id}"));
+        0
+    }
 }
 
 pub(crate) fn mpv_free_stub(_data: *mut c_void) {
@@ -16,3 +63,725 @@ pub(crate) fn mpv_free_stub(_data: *mut c_void) {
 pub(crate) fn mpv_free_node_contents_stub(_node: *mut mpv_node) {
     MPV_FREE_NODE_CONTENTS_CALLS.set(MPV_FREE_NODE_CONTENTS_CALLS.get() + 1);
 }
+
+#[test]
+fn unknown_values_from_newer_mpv() {
+    use std::ffi::c_int;
+    use std::ptr::null_mut;
+    use libmpv_client_sys::{mpv_event, mpv_event_end_file, mpv_event_id_MPV_EVENT_END_FILE, mpv_node__bindgen_ty_1};
+    use crate::*;
+    use crate::error::RustError;
+    use crate::event::{EndFile, EndFileReason, PropertyValue};
+    use crate::types::traits::MpvSendInternal;
+
+    let event = mpv_event { event_id: 999, error: 0, reply_userdata: 0, data: null_mut() };
+    assert!(matches!(Event::from_ptr(&event), Ok(Event::Unknown(999))));
+
+    let mut end_file = mpv_event_end_file { reason: 999, error: 0, playlist_entry_id: 1, playlist_insert_id: 0, playlist_insert_num_entries: 0 };
+    let event = mpv_event { event_id: mpv_event_id_MPV_EVENT_END_FILE, error: 0, reply_userdata: 0, data: &raw mut end_file as *mut c_void };
+    assert!(matches!(Event::from_ptr(&event), Ok(Event::EndFile(EndFile { reason: EndFileReason::Unknown(999), .. }))));
+
+    assert!(matches!(unsafe { PropertyValue::from_mpv(999, null_mut()) }, Ok(PropertyValue::Unknown(999))));
+    assert!(matches!(Error::from(-999), Error::Unknown(-999)));
+    assert_eq!(c_int::from(&Error::Unknown(-999)), -999);
+
+    let node = mpv_node { u: mpv_node__bindgen_ty_1 { int64: 0 }, format: 999 };
+    let node = unsafe { Node::from_node_ptr(&node) }.unwrap();
+    assert_eq!(node, Node::Unknown(999));
+    assert!(matches!(node.to_mpv(|_| Ok(0)), Err(Error::Rust(RustError::UnknownNodeFormat(999)))));
+}
+
+#[test]
+fn observer_decodes_property_values() {
+    use crate::*;
+    use crate::event::PropertyValue;
+    use crate::observer::decode;
+
+    assert_eq!(decode::<f64>(&PropertyValue::Double(1.5)).unwrap(), Some(1.5));
+    assert_eq!(decode::<u32>(&PropertyValue::Int64(7)).unwrap(), Some(7));
+    assert!(decode::<u32>(&PropertyValue::Int64(-1)).is_err());
+    assert_eq!(decode::<String>(&PropertyValue::String("a".to_string())).unwrap(), Some("a".to_string()));
+    assert_eq!(decode::<bool>(&PropertyValue::None).unwrap(), None);
+    assert!(matches!(decode::<bool>(&PropertyValue::Int64(1)), Err(Error::PropertyFormat)));
+
+    let node = node_map! { ("a", 1) };
+    assert_eq!(decode::<Node>(&PropertyValue::Node(node.clone())).unwrap(), Some(node));
+    assert_eq!(decode::<Vec<i64>>(&PropertyValue::Node(node_array!(1, 2))).unwrap(), Some(vec![1, 2]));
+}
+
+#[test]
+fn observer_userdata_is_unique() {
+    use crate::observer::next_userdata;
+
+    let a = next_userdata();
+    let b = next_userdata();
+    assert_ne!(a, b);
+    assert!(a >= 1 << 63 && b >= 1 << 63);
+}
+
+#[cfg(any(feature = "log", feature = "tracing"))]
+#[test]
+fn log_message_text_strips_newline() {
+    use crate::event::{LogLevel, LogMessage};
+    use crate::logging::text;
+
+    let message = LogMessage { level: LogLevel::Info, prefix: "cplayer".to_string(), text: "Playing: a.mkv\n".to_string() };
+    assert_eq!(text(&message), "Playing: a.mkv");
+
+    let message = LogMessage { text: "no newline".to_string(), ..message };
+    assert_eq!(text(&message), "no newline");
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn log_messages_forwarded_to_log() {
+    use std::sync::Mutex;
+    use ::log::{Level, Log, Metadata, Record};
+    use crate::event::{LogLevel, LogMessage};
+    use crate::logging::{to_log, OVERFLOW_PREFIX};
+
+    static RECORDS: Mutex<Vec<(Level, String, String)>> = Mutex::new(Vec::new());
+
+    struct Capture;
+    impl Log for Capture {
+        fn enabled(&self, _: &Metadata) -> bool { true }
+        fn log(&self, record: &Record) {
+            if record.target().starts_with("mpv") {
+                RECORDS.lock().unwrap().push((record.level(), record.target().to_string(), record.args().to_string()));
+            }
+        }
+        fn flush(&self) {}
+    }
+
+    ::log::set_logger(&Capture).unwrap();
+    ::log::set_max_level(::log::LevelFilter::Trace);
+
+    to_log(&LogMessage { level: LogLevel::Verbose, prefix: "ffmpeg".to_string(), text: "probing\n".to_string() });
+    to_log(&LogMessage { level: LogLevel::Warn, prefix: OVERFLOW_PREFIX.to_string(), text: "log buffer full\n".to_string() });
+
+    assert_eq!(*RECORDS.lock().unwrap(), [
+        (Level::Debug, "mpv::ffmpeg".to_string(), "probing".to_string()),
+        (Level::Warn, "mpv".to_string(), "mpv dropped log messages: log buffer full".to_string()),
+    ]);
+}
+
+#[test]
+fn on_load_playlist_and_edl_urls() {
+    use crate::Error;
+    use crate::hooks::{edl_url, playlist_url};
+
+    assert_eq!(playlist_url(&["https://a.example/1", "b.mkv"]).unwrap(), "memory://#EXTM3U\nhttps://a.example/1\nb.mkv\n");
+    assert!(matches!(playlist_url(&["a\nb"]), Err(Error::InvalidParameter)));
+
+    assert_eq!(edl_url(&[("a;b.mkv", ""), ("c.mkv", ",length=10")]), "edl://%7%a;b.mkv;%5%c.mkv,length=10");
+}
+
+#[test]
+fn on_load_recognizes_hooks() {
+    use crate::event::Hook;
+    use crate::hooks::{OnLoad, ON_LOAD, ON_LOAD_FAIL};
+
+    let hook = |name: &str| Hook { name: name.to_string(), id: 1, userdata: 0 };
+    assert!(OnLoad::is_on_load(&hook(ON_LOAD)));
+    assert!(OnLoad::is_on_load(&hook(ON_LOAD_FAIL)));
+    assert!(!OnLoad::is_on_load(&hook("on_unload")));
+}
+
+/// A resolver for URLs starting with its prefix, which resolves them to the prefix.
+struct PrefixResolver(&'static str);
+
+impl crate::resolver::Resolver for PrefixResolver {
+    fn matches(&self, url: &str) -> bool {
+        url.starts_with(self.0)
+    }
+
+    fn resolve(&self, _: &str) -> crate::Result<crate::resolver::Resolution> {
+        Ok(crate::resolver::Resolution::Url(self.0.to_string()))
+    }
+}
+
+#[test]
+fn resolvers_by_priority() {
+    use crate::resolver::{Resolution, Resolvers};
+
+    let mut resolvers = Resolvers::default();
+    resolvers.add(10, Box::new(PrefixResolver("catalog://")));
+    resolvers.add(0, Box::new(PrefixResolver("catalog://a")));
+    resolvers.add(10, Box::new(PrefixResolver("cat")));
+
+    let first = |url| resolvers.find(url).map(|r| match r.resolve(url).unwrap() {
+        Resolution::Url(url) => url,
+        _ => unreachable!(),
+    });
+    assert_eq!(first("catalog://a1").as_deref(), Some("catalog://a"));
+    assert_eq!(first("catalog://b1").as_deref(), Some("catalog://"));
+    assert_eq!(first("cats.mkv").as_deref(), Some("cat"));
+    assert_eq!(first("https://example.com"), None);
+}
+
+#[test]
+fn resolver_track_commands() {
+    use crate::resolver::ExternalTrack;
+
+    let track = ExternalTrack { url: "a.opus".to_string(), ..Default::default() };
+    assert_eq!(track.command("audio-add"), ["audio-add", "a.opus", "auto"]);
+
+    let track = ExternalTrack { lang: Some("en".to_string()), ..track };
+    assert_eq!(track.command("sub-add"), ["sub-add", "a.opus", "auto", "", "en"]);
+
+    let track = ExternalTrack { title: Some("English".to_string()), lang: None, ..track };
+    assert_eq!(track.command("sub-add"), ["sub-add", "a.opus", "auto", "English"]);
+}
+
+#[test]
+fn failing_resolver_does_not_stop_the_hook() {
+    use std::cell::RefCell;
+    use crate::*;
+    use crate::resolver::{LoadTarget, Resolution, Resolver, Resolvers, load};
+
+    struct Failing;
+
+    impl Resolver for Failing {
+        fn matches(&self, url: &str) -> bool {
+            url.starts_with("broken://")
+        }
+
+        fn resolve(&self, _: &str) -> Result<Resolution> {
+            Err(Error::LoadingFailed)
+        }
+    }
+
+    #[derive(Default)]
+    struct Target {
+        url: RefCell<String>,
+        reports: RefCell<Vec<String>>,
+    }
+
+    impl LoadTarget for Target {
+        fn url(&self) -> Result<String> {
+            Ok(self.url.borrow().clone())
+        }
+
+        fn set_url(&self, url: &str) -> Result<()> {
+            self.url.replace(url.to_string());
+            Ok(())
+        }
+
+        fn add_playlist(&self, _: &[String]) -> Result<()> {
+            unreachable!()
+        }
+
+        fn report(&self, context: &str, _: &Error) {
+            self.reports.borrow_mut().push(context.to_string());
+        }
+    }
+
+    let mut resolvers = Resolvers::default();
+    resolvers.add(0, Box::new(Failing));
+    resolvers.add(10, Box::new(PrefixResolver("catalog://")));
+    let pending = RefCell::new(None);
+
+    let target = Target { url: RefCell::new("broken://a".to_string()), ..Default::default() };
+    assert!(load(&resolvers, &target, &pending).is_ok());
+    assert_eq!(*target.url.borrow(), "broken://a");
+    assert_eq!(*target.reports.borrow(), ["failed to resolve broken://a"]);
+
+    // The next file is still resolved.
+    target.url.replace("catalog://b".to_string());
+    assert!(load(&resolvers, &target, &pending).is_ok());
+    assert_eq!(*target.url.borrow(), "catalog://");
+    assert_eq!(target.reports.borrow().len(), 1);
+}
+
+#[test]
+fn cplugin_exit_codes() {
+    use crate::Error;
+    use crate::cplugin::exit_code;
+
+    assert_eq!(exit_code(Ok(())), 0);
+    assert_eq!(exit_code(Err(Error::PropertyNotFound)), libmpv_client_sys::mpv_error_MPV_ERROR_PROPERTY_NOT_FOUND);
+    assert_eq!(exit_code(Err(Error::Unknown(-99))), -99);
+}
+
+#[test]
+fn panic_guard_reports_and_recovers() {
+    use std::panic::catch_unwind;
+    use std::ptr::null_mut;
+    use std::sync::Mutex;
+    use crate::panic::{guard, guard_callback, reset_panic_hook, set_panic_hook, BOUNDARY};
+
+    static REPORTS: Mutex<Vec<(&'static str, String, bool, bool)>> = Mutex::new(Vec::new());
+    set_panic_hook(|report| {
+        let location = report.location.is_some_and(|location| location.contains("tests.rs"));
+        REPORTS.lock().unwrap().push((report.boundary, report.message.to_string(), report.handle().is_some(), location));
+    });
+
+    assert_eq!(guard("test", null_mut(), || 1, || 2), 2);
+    assert_eq!(guard("test", null_mut(), || 1, || panic!("boom {}", 42)), 1);
+    guard_callback("callback", || guard_callback("nested", || panic!("inner")));
+    guard_callback("caught", || assert!(catch_unwind(|| panic!("caught")).is_err()));
+
+    // Replacing the process-wide hook loses the location, but not the report.
+    let previous = std::panic::take_hook();
+    guard_callback("replaced", || panic!("replaced"));
+    std::panic::set_hook(previous);
+
+    reset_panic_hook();
+    assert_eq!(*REPORTS.lock().unwrap(), [
+        ("test", "boom 42".to_string(), false, true),
+        ("nested", "inner".to_string(), false, true),
+        ("replaced", "replaced".to_string(), false, false),
+    ]);
+    assert_eq!(BOUNDARY.get(), None);
+}
+
+#[cfg(feature = "instrument")]
+#[test]
+fn instrumented_command_names_and_formats() {
+    use crate::*;
+    use crate::instrument::{command_name, format_name, node_formats};
+
+    let command = node_array!("seek", 10, "relative");
+    assert_eq!(command_name(&command), "seek");
+    assert_eq!(node_formats(&command), ["string", "int64", "string"]);
+
+    let command = node_map! { ("name", "show-text"), ("duration", 500) };
+    assert_eq!(command_name(&command), "show-text");
+    assert_eq!(node_formats(&command).len(), 2);
+
+    assert_eq!(command_name(&Node::Int64(1)), "");
+    assert_eq!(format_name(Format::OSD_STRING.0), "osd-string");
+}
+
+#[test]
+fn player_state_track_ids() {
+    use crate::Node;
+    use crate::state::track_id;
+
+    assert_eq!(track_id(Some(Node::Int64(2))), Some(2));
+    assert_eq!(track_id(Some(Node::String("no".to_string()))), None);
+    assert_eq!(track_id(None), None);
+}
+
+#[test]
+fn player_state_snapshots_can_be_sent() {
+    use std::sync::Arc;
+    use crate::state::PlayerState;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Arc<PlayerState>>();
+}
+
+#[test]
+fn player_state_updates_from_events() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use crate::*;
+    use crate::event::{EndFile, EndFileReason, PropertyChange, PropertyValue};
+    use crate::state::PlayerStateTracker;
+
+    let tracker = PlayerStateTracker::new();
+    let changes = Rc::new(Cell::new(0));
+    let counter = Rc::clone(&changes);
+    tracker.on_change(move |previous, current| {
+        assert_ne!(previous, current);
+        counter.set(counter.get() + 1);
+    });
+
+    let change = |name: &str, value: PropertyValue, userdata: u64| Event::PropertyChange(PropertyChange {
+        name: name.to_string(),
+        value: Ok(value),
+        userdata,
+    });
+
+    assert!(tracker.update(&change("pause", PropertyValue::Flag(true), tracker.userdata())).unwrap());
+    assert!(tracker.update(&change("aid", PropertyValue::Node(Node::Int64(2)), tracker.userdata())).unwrap());
+    assert!(tracker.update(&change("playlist-pos", PropertyValue::Int64(-1), tracker.userdata())).is_ok_and(|changed| !changed));
+    assert!(tracker.update(&change("media-title", PropertyValue::String("title".to_string()), tracker.userdata())).unwrap());
+
+    // The same value again, a property observed elsewhere, and an unrelated event don't publish anything.
+    assert!(!tracker.update(&change("pause", PropertyValue::Flag(true), tracker.userdata())).unwrap());
+    assert!(!tracker.update(&change("pause", PropertyValue::Flag(false), tracker.userdata() + 1)).unwrap());
+    assert!(!tracker.update(&Event::FileLoaded).unwrap());
+
+    let end_file = Event::EndFile(EndFile {
+        reason: EndFileReason::Eof,
+        playlist_entry_id: 1,
+        playlist_insert_id: 0,
+        playlist_insert_num_entries: 0,
+    });
+    assert!(tracker.update(&end_file).unwrap());
+    assert!(!tracker.update(&end_file).unwrap());
+
+    let state = tracker.snapshot();
+    assert!(state.paused);
+    assert_eq!(state.tracks.audio, Some(2));
+    assert_eq!(state.playlist_pos, None);
+    assert_eq!(state.title.as_deref(), Some("title"));
+    assert_eq!(state.last_end_file, Some(EndFileReason::Eof));
+    assert_eq!(changes.get(), 4);
+
+    // A value in the wrong format is an error, and leaves the state as it was.
+    assert!(tracker.update(&change("pause", PropertyValue::Int64(0), tracker.userdata())).is_err());
+    assert!(tracker.snapshot().paused);
+}
+
+#[test]
+fn failing_state_update_does_not_stop_the_handler() {
+    use std::cell::RefCell;
+    use crate::*;
+    use crate::event::{PropertyChange, PropertyValue};
+    use crate::state::PlayerStateTracker;
+
+    let tracker = PlayerStateTracker::new();
+    let reports = RefCell::new(Vec::new());
+    let change = |name: &str, value: PropertyValue| Event::PropertyChange(PropertyChange {
+        name: name.to_string(),
+        value: Ok(value),
+        userdata: tracker.userdata(),
+    });
+
+    tracker.handle_event(&change("pause", PropertyValue::Int64(1)), |context, _| reports.borrow_mut().push(context.to_string()));
+    assert_eq!(*reports.borrow(), ["failed to update the player state from pause"]);
+    assert!(!tracker.snapshot().paused);
+
+    // Later changes are still applied.
+    tracker.handle_event(&change("pause", PropertyValue::Flag(true)), |context, _| reports.borrow_mut().push(context.to_string()));
+    assert_eq!(reports.borrow().len(), 1);
+    assert!(tracker.snapshot().paused);
+}
+
+#[test]
+fn dispatcher_routes_property_changes_by_userdata() {
+    use std::cell::RefCell;
+    use std::ffi::CString;
+    use std::ptr::null_mut;
+    use libmpv_client_sys::{mpv_event, mpv_event_id_MPV_EVENT_PROPERTY_CHANGE, mpv_event_property, mpv_format_MPV_FORMAT_INT64};
+    use crate::*;
+    use crate::dispatch::Dispatcher;
+
+    let values = RefCell::new(Vec::new());
+    let mut dispatcher = Dispatcher::new(Handle::from_ptr(null_mut()));
+    let volume = dispatcher.on_property::<i64>("volume", |_, value| {
+        values.borrow_mut().push(("volume", value));
+        Ok(())
+    }).unwrap();
+    dispatcher.on_property::<i64>("speed", |_, value| {
+        values.borrow_mut().push(("speed", value));
+        Ok(())
+    }).unwrap();
+
+    let name = CString::new("volume").unwrap();
+    let mut data = 50i64;
+    let mut property = mpv_event_property { name: name.as_ptr(), format: mpv_format_MPV_FORMAT_INT64, data: &raw mut data as *mut c_void };
+    let mut event = mpv_event {
+        event_id: mpv_event_id_MPV_EVENT_PROPERTY_CHANGE,
+        error: 0,
+        reply_userdata: volume.0,
+        data: &raw mut property as *mut c_void,
+    };
+    dispatcher.dispatch(&mut event).unwrap();
+
+    // An unavailable property has no data, and a change with an unknown userdata reaches no handler.
+    let mut unavailable = mpv_event_property { data: null_mut(), ..property };
+    event.data = &raw mut unavailable as *mut c_void;
+    dispatcher.dispatch(&mut event).unwrap();
+    event.reply_userdata = 7;
+    dispatcher.dispatch(&mut event).unwrap();
+
+    assert_eq!(*values.borrow(), [("volume", Some(50)), ("volume", None)]);
+}
+
+#[test]
+fn dispatcher_matches_client_message_names() {
+    use std::cell::RefCell;
+    use std::ffi::{CString, c_char};
+    use std::ptr::null_mut;
+    use libmpv_client_sys::{mpv_event, mpv_event_client_message, mpv_event_id_MPV_EVENT_CLIENT_MESSAGE};
+    use crate::*;
+    use crate::dispatch::Dispatcher;
+
+    let messages = RefCell::new(Vec::new());
+    let mut dispatcher = Dispatcher::new(Handle::from_ptr(null_mut()));
+    dispatcher.on_client_message("seek-to", |_, args| {
+        messages.borrow_mut().push(("seek-to", args.to_vec()));
+        Ok(())
+    });
+    dispatcher.on_client_message("seek", |_, args| {
+        messages.borrow_mut().push(("seek", args.to_vec()));
+        Ok(())
+    });
+
+    let args = [CString::new("seek").unwrap(), CString::new("10").unwrap()];
+    let mut arg_ptrs: Vec<*const c_char> = args.iter().map(|arg| arg.as_ptr()).collect();
+    let mut message = mpv_event_client_message { num_args: 2, args: arg_ptrs.as_mut_ptr() };
+    let mut event = mpv_event {
+        event_id: mpv_event_id_MPV_EVENT_CLIENT_MESSAGE,
+        error: 0,
+        reply_userdata: 0,
+        data: &raw mut message as *mut c_void,
+    };
+    dispatcher.dispatch(&mut event).unwrap();
+
+    // A message without arguments has no name, and reaches no handler.
+    let mut empty = mpv_event_client_message { num_args: 0, ..message };
+    event.data = &raw mut empty as *mut c_void;
+    dispatcher.dispatch(&mut event).unwrap();
+
+    assert_eq!(*messages.borrow(), [("seek", vec!["10".to_string()])]);
+}
+
+#[test]
+fn dispatcher_continues_removed_hooks() {
+    use std::cell::Cell;
+    use std::ffi::CString;
+    use std::ptr::null_mut;
+    use libmpv_client_sys::{mpv_event, mpv_event_hook, mpv_event_id_MPV_EVENT_HOOK};
+    use crate::*;
+    use crate::dispatch::Dispatcher;
+
+    let calls = Cell::new(0);
+    let mut dispatcher = Dispatcher::new(Handle::from_ptr(null_mut()));
+    let hook = dispatcher.on_hook("on_load", 0, |_, _| {
+        calls.set(calls.get() + 1);
+        Ok(())
+    }).unwrap();
+
+    let name = CString::new("on_load").unwrap();
+    let mut data = mpv_event_hook { name: name.as_ptr(), id: 42 };
+    let mut event = mpv_event {
+        event_id: mpv_event_id_MPV_EVENT_HOOK,
+        error: 0,
+        reply_userdata: hook.0,
+        data: &raw mut data as *mut c_void,
+    };
+    MPV_CALLS.take();
+    dispatcher.dispatch(&mut event).unwrap();
+    assert_eq!(calls.get(), 1);
+
+    // mpv keeps sending the hook, which must still be continued.
+    assert!(dispatcher.remove(hook).unwrap());
+    assert!(!dispatcher.remove(hook).unwrap());
+    let mut data = mpv_event_hook { id: 43, ..data };
+    event.data = &raw mut data as *mut c_void;
+    dispatcher.dispatch(&mut event).unwrap();
+    assert_eq!(calls.get(), 1);
+
+    assert_eq!(MPV_CALLS.take(), ["hook_continue 42", "hook_continue 43"]);
+}
+
+#[test]
+fn dispatcher_only_decodes_wanted_events() {
+    use std::ffi::CString;
+    use std::ptr::null_mut;
+    use libmpv_client_sys::{mpv_event, mpv_event_id_MPV_EVENT_LOG_MESSAGE, mpv_event_id_MPV_EVENT_PROPERTY_CHANGE, mpv_event_property, mpv_format_MPV_FORMAT_INT64};
+    use crate::*;
+    use crate::dispatch::Dispatcher;
+
+    let mut dispatcher = Dispatcher::new(Handle::from_ptr(null_mut()));
+
+    // A log message without data can't be decoded, which only matters once a handler wants it.
+    let mut log_message = mpv_event { event_id: mpv_event_id_MPV_EVENT_LOG_MESSAGE, error: 0, reply_userdata: 0, data: null_mut() };
+    dispatcher.dispatch(&mut log_message).unwrap();
+    let id = dispatcher.on_event(EventId::LOG_MESSAGE, |_, _| Ok(())).unwrap();
+    assert!(dispatcher.dispatch(&mut log_message).is_err());
+    dispatcher.remove(id).unwrap();
+
+    // A property name which isn't UTF-8 fails the generic decoding, but not the property handler which already got the value.
+    let mut value = None;
+    let speed = dispatcher.on_property::<i64>("speed", |_, v| {
+        value = v;
+        Ok(())
+    }).unwrap();
+    dispatcher.on_event(EventId::PROPERTY_CHANGE, |_, _| Ok(())).unwrap();
+
+    let name = CString::new(b"sp\xFFed".to_vec()).unwrap();
+    let mut data = 2i64;
+    let mut property = mpv_event_property { name: name.as_ptr(), format: mpv_format_MPV_FORMAT_INT64, data: &raw mut data as *mut c_void };
+    let mut event = mpv_event {
+        event_id: mpv_event_id_MPV_EVENT_PROPERTY_CHANGE,
+        error: 0,
+        reply_userdata: speed.0,
+        data: &raw mut property as *mut c_void,
+    };
+    dispatcher.dispatch(&mut event).unwrap();
+
+    event.reply_userdata = 7;
+    assert!(dispatcher.dispatch(&mut event).is_err());
+
+    drop(dispatcher);
+    assert_eq!(value, Some(2));
+}
+
+#[test]
+fn dispatcher_counts_overflows_and_resyncs() {
+    use std::cell::RefCell;
+    use std::ptr::null_mut;
+    use libmpv_client_sys::{mpv_event, mpv_event_id_MPV_EVENT_QUEUE_OVERFLOW};
+    use crate::*;
+    use crate::dispatch::Dispatcher;
+
+    let values = RefCell::new(Vec::new());
+    let mut dispatcher = Dispatcher::new(Handle::from_ptr(null_mut()));
+    dispatcher.on_property::<f64>("volume", |_, value| {
+        values.borrow_mut().push(("volume", value.is_some()));
+        Ok(())
+    }).unwrap();
+    dispatcher.on_property::<String>("path", |_, value| {
+        values.borrow_mut().push(("path", value.is_some()));
+        Ok(())
+    }).unwrap();
+
+    let mut event = mpv_event { event_id: mpv_event_id_MPV_EVENT_QUEUE_OVERFLOW, error: 0, reply_userdata: 0, data: null_mut() };
+    MPV_CALLS.take();
+    dispatcher.dispatch(&mut event).unwrap();
+    assert_eq!(dispatcher.overflow_count(), 1);
+    assert!(MPV_CALLS.take().is_empty());
+
+    // Each property is read again, and is unavailable with the stand-in for mpv_get_property().
+    dispatcher.set_resync_on_overflow(true);
+    dispatcher.dispatch(&mut event).unwrap();
+    assert_eq!(dispatcher.overflow_count(), 2);
+    assert_eq!(MPV_CALLS.take(), ["get_property volume", "get_property path"]);
+
+    drop(dispatcher);
+    assert_eq!(*values.borrow(), [("volume", false), ("path", false)]);
+}
+
+#[test]
+fn ass_escapes_text() {
+    use crate::ass::escape;
+
+    assert_eq!(escape("a {b} c"), "a \\{b\\} c");
+    assert_eq!(escape("C:\\N"), "C:\\\u{FEFF}N");
+    assert_eq!(escape("one\n  two"), "one\u{FEFF}\\N\\h\\htwo");
+    assert_eq!(escape(" lead"), "\\hlead");
+}
+
+#[test]
+fn ass_colors() {
+    use crate::ass::Color;
+
+    let color = Color::rgba(0x11, 0x22, 0x33, 0xFF);
+    assert_eq!(color.to_ass_color(), "&H332211&");
+    assert_eq!(color.to_ass_alpha(), "&H00&");
+    assert_eq!(Color::from_hex(0x112233), color);
+    assert_eq!(Color::TRANSPARENT.to_ass_alpha(), "&HFF&");
+}
+
+#[test]
+fn ass_builder() {
+    use crate::ass::{Alignment, Ass, Color};
+
+    let mut ass = Ass::new();
+    ass.pos(10.0, 20.5).an(Alignment::TopLeft).font_size(30.0).color(Color::RED)
+        .text("{title}")
+        .bold(true)
+        .text(" $5");
+
+    assert_eq!(ass.as_str(), "{\\pos(10,20.5)\\an7\\fs30\\1c&H0000FF&\\1a&H00&}\\{title\\}{\\b1}\\h$5");
+    assert_eq!(ass.to_show_text(), "${osd-ass-cc/0}{\\pos(10,20.5)\\an7\\fs30\\1c&H0000FF&\\1a&H00&}\\{title\\}{\\b1}\\h$$5${osd-ass-cc/1}");
+}
+
+#[test]
+fn ass_drawing() {
+    use crate::ass::{Ass, Drawing};
+
+    let mut ass = Ass::new();
+    ass.text("a").new_event().pos(0.0, 0.0).draw(Drawing::new().rect(0.0, 0.0, 10.0, 5.5));
+
+    assert_eq!(ass.as_str(), "a\n{\\pos(0,0)\\p1}m 0 0 l 10 0 l 10 5.5 l 0 5.5{\\p0}");
+}
+
+#[test]
+fn overlay_premultiplies_colors() {
+    use crate::ass::Color;
+    use crate::overlay::premultiply;
+
+    assert_eq!(premultiply(Color::rgb(0x10, 0x20, 0x30)), [0x30, 0x20, 0x10, 0xFF]);
+    assert_eq!(premultiply(Color::rgba(255, 128, 0, 128)), [0, 64, 128, 128]);
+    assert_eq!(premultiply(Color::TRANSPARENT), [0, 0, 0, 0]);
+}
+
+#[test]
+fn subprocess_to_node() {
+    use crate::*;
+    use crate::subprocess::Subprocess;
+
+    let node = Subprocess::new("echo").arg("hi").env("A", "1").capture_stdout(true).to_node();
+
+    let Node::Map(map) = node else { panic!("expected a map") };
+    assert_eq!(map["name"], Node::from("subprocess"));
+    assert_eq!(map["args"], node_array!("echo", "hi"));
+    assert_eq!(map["env"], node_array!("A=1"));
+    assert_eq!(map["capture_stdout"], Node::Flag(true));
+    assert_eq!(map["playback_only"], Node::Flag(true));
+    assert!(!map.contains_key("stdin_data"));
+}
+
+#[test]
+fn subprocess_result_from_node() {
+    use crate::*;
+    use crate::subprocess::SubprocessResult;
+
+    let result = SubprocessResult::from_node(node_map! {
+        ("status", 0),
+        ("stdout", Node::ByteArray(b"hi\n".to_vec())),
+        ("error_string", ""),
+        ("killed_by_us", false),
+    }).unwrap();
+
+    assert!(result.success());
+    assert_eq!(result.stdout_str().unwrap(), "hi\n");
+    assert!(result.stderr.is_empty());
+
+    assert!(SubprocessResult::from_node(Node::None).is_err());
+}
+
+/// A 2x2 `bgr0` screenshot with 4 bytes of row padding.
+fn screenshot_test_image() -> crate::screenshot::Image {
+    use crate::*;
+    use crate::screenshot::Image;
+
+    Image::from_node(node_map! {
+        ("w", 2),
+        ("h", 2),
+        ("stride", 12),
+        ("format", "bgr0"),
+        ("data", Node::ByteArray(vec![
+            3, 2, 1, 0, 6, 5, 4, 0, 9, 9, 9, 9,
+            9, 8, 7, 0, 0, 0, 0, 0, 9, 9, 9, 9,
+        ])),
+    }).unwrap()
+}
+
+#[test]
+fn screenshot_decodes_images() {
+    use crate::*;
+    use crate::screenshot::{Image, PixelFormat};
+
+    let image = screenshot_test_image();
+    assert_eq!(image.format, PixelFormat::Bgr0);
+    assert_eq!(image.to_rgba().unwrap(), [1, 2, 3, 255, 4, 5, 6, 255, 7, 8, 9, 255, 0, 0, 0, 255]);
+
+    assert!(Image::from_node(node_map! {
+        ("w", 2), ("h", 2), ("stride", 8), ("format", "bgr0"), ("data", Node::ByteArray(vec![0; 8])),
+    }).is_err());
+}
+
+#[test]
+fn screenshot_encodes_images() {
+    use crate::screenshot::adler32;
+
+    let image = screenshot_test_image();
+
+    let ppm = image.to_ppm().unwrap();
+    assert!(ppm.starts_with(b"P6\n2 2\n255\n"));
+    assert_eq!(&ppm[11..], [1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0]);
+
+    let png = image.to_png().unwrap();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"));
+    assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+
+    assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+}
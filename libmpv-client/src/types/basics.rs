@@ -1,6 +1,9 @@
 use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::fmt::Display;
 use std::ptr::null_mut;
+use std::time::Duration;
 use crate::*;
+use crate::error::{RangeError, RustError};
 use crate::types::traits::{MpvFormat, MpvRecv, MpvRecvInternal, MpvSend, MpvSendInternal};
 
 /// A wrapper around [`String`] for mpv OSD property strings. See [`Format::OSD_STRING`].
@@ -192,4 +195,103 @@ impl MpvSendInternal for f64 {
     fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
         fun(self as *const Self as *mut c_void)
     }
-}
\ No newline at end of file
+}
+
+/// Convert `value` into `T`, failing with [`RustError::OutOfRange`] if it doesn't fit.
+pub(crate) fn range_checked<T: TryFrom<U>, U: Display + Copy>(value: U, target: &'static str) -> Result<T> {
+    match T::try_from(value) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(Error::Rust(RustError::OutOfRange(RangeError { value: value.to_string(), target }))),
+    }
+}
+
+/// Implement the send/recv traits for integer types, through [`Format::INT64`] with range checks in both directions.
+macro_rules! checked_int {
+    ($($t:ty),*) => {$(
+        impl MpvFormat for $t {
+            const MPV_FORMAT: Format = Format::INT64;
+        }
+
+        impl MpvRecv for $t {}
+        impl MpvRecvInternal for $t {
+            unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
+                range_checked(unsafe { i64::from_ptr(ptr)? }, stringify!($t))
+            }
+
+            unsafe fn from_mpv<F: Fn(*mut c_void) -> Result<i32>>(fun: F) -> Result<Self> {
+                range_checked(unsafe { i64::from_mpv(fun)? }, stringify!($t))
+            }
+        }
+
+        impl MpvSend for $t {}
+        impl MpvSendInternal for $t {
+            fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+                range_checked::<i64, _>(*self, "i64")?.to_mpv(fun)
+            }
+        }
+    )*};
+}
+
+checked_int!(i32, u32, u64, usize);
+
+impl MpvFormat for f32 {
+    const MPV_FORMAT: Format = Format::DOUBLE;
+}
+
+/// Narrow `value` to [`f32`], failing with [`RustError::OutOfRange`] if it is finite but too large.
+pub(crate) fn f32_checked(value: f64) -> Result<f32> {
+    if value.is_finite() && value.abs() > f32::MAX as f64 {
+        return Err(Error::Rust(RustError::OutOfRange(RangeError { value: value.to_string(), target: "f32" })));
+    }
+    Ok(value as f32)
+}
+
+impl MpvRecv for f32 {}
+impl MpvRecvInternal for f32 {
+    unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
+        f32_checked(unsafe { f64::from_ptr(ptr)? })
+    }
+
+    unsafe fn from_mpv<F: Fn(*mut c_void) -> Result<i32>>(fun: F) -> Result<Self> {
+        f32_checked(unsafe { f64::from_mpv(fun)? })
+    }
+}
+
+impl MpvSend for f32 {}
+impl MpvSendInternal for f32 {
+    fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+        f64::from(*self).to_mpv(fun)
+    }
+}
+
+/// Convert a number of seconds into a [`Duration`], failing with [`RustError::OutOfRange`] if it is negative, too large or not a number.
+pub(crate) fn duration_checked(secs: f64) -> Result<Duration> {
+    Duration::try_from_secs_f64(secs)
+        .map_err(|_| Error::Rust(RustError::OutOfRange(RangeError { value: secs.to_string(), target: "Duration" })))
+}
+
+impl MpvFormat for Duration {
+    const MPV_FORMAT: Format = Format::DOUBLE;
+}
+
+/// Time properties such as `time-pos` or `duration` can be received as a [`Duration`], as they are in seconds.
+///
+/// Negative times (such as `time-pos` shortly before the start of a file with a start offset) fail with [`RustError::OutOfRange`].
+impl MpvRecv for Duration {}
+impl MpvRecvInternal for Duration {
+    unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
+        duration_checked(unsafe { f64::from_ptr(ptr)? })
+    }
+
+    unsafe fn from_mpv<F: Fn(*mut c_void) -> Result<i32>>(fun: F) -> Result<Self> {
+        duration_checked(unsafe { f64::from_mpv(fun)? })
+    }
+}
+
+/// A [`Duration`] is sent as a number of seconds.
+impl MpvSend for Duration {}
+impl MpvSendInternal for Duration {
+    fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+        self.as_secs_f64().to_mpv(fun)
+    }
+}
@@ -61,7 +61,7 @@ impl MpvRecvInternal for ByteArray {
 impl MpvSend for ByteArray {}
 impl MpvSendInternal for ByteArray {
     fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
-        let repr = self.to_mpv_repr();
+        let repr = self.to_mpv_repr()?;
 
         fun(repr.ptr() as *mut c_void)
     }
@@ -70,13 +70,13 @@ impl MpvSendInternal for ByteArray {
 impl ToMpvRepr for ByteArray {
     type ReprWrap<'a> = MpvByteArray<'a>;
 
-    fn to_mpv_repr(&self) -> Self::ReprWrap<'_> {
-        MpvByteArray {
+    fn to_mpv_repr(&self) -> Result<Self::ReprWrap<'_>> {
+        Ok(MpvByteArray {
             _original: PhantomData,
             byte_array: Box::new(mpv_byte_array {
                 data: self.as_ptr() as *mut c_void,
                 size: self.len(),
             }),
-        }
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,497 @@
+//! Conversions between user-defined types and [`Node`], which let those types be used with
+//! [`Handle::get_property()`], [`Handle::set_property()`] and other functions taking [`MpvRecv`]/[`MpvSend`] types.
+
+use std::collections::HashMap;
+use std::ffi::{c_void, OsString};
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::*;
+use crate::basics::{duration_checked, f32_checked};
+use crate::error::{NodeError, RustError};
+use crate::types::traits::{MpvRecvInternal, MpvSendInternal};
+
+/// A type which can be converted from a [`Node`].
+///
+/// This is implemented for [`Node`], [`String`], [`OsString`](std::ffi::OsString), [`PathBuf`](std::path::PathBuf), [`bool`],
+/// integers (with range checks), [`f64`], and for [`Option`], [`Vec`]
+/// and [`HashMap<String, T>`] of those. Types deriving this with `#[derive(MpvFormat, MpvRecv)]` (which requires the `derive` feature)
+/// are also [`MpvRecv`] through [`Format::NODE`].
+///
+/// # Derive
+/// - Structs with named fields are read from a [`Node::Map`]. Field names are converted to kebab-case to match mpv's naming.
+/// - Newtype structs are read transparently from their only field, and other tuple structs from a [`Node::Array`].
+/// - Enums with only unit variants are read from a [`Node::String`] of the kebab-case variant name.
+///   [`Node::Flag`] is accepted as `"yes"` or `"no"`.
+///
+/// `#[derive(MpvFormat)]` declares that the type is sent and received through [`Format::NODE`], and is required by both
+/// `#[derive(MpvRecv)]` and `#[derive(MpvSend)]`, so that types deriving both have a single [`MpvFormat`].
+///
+/// The following attributes are supported:
+/// - `#[mpv(rename_all = "...")]` on a struct with named fields or an enum, to use `"snake_case"`, `"camelCase"`, `"PascalCase"`, `"lowercase"`, `"UPPERCASE"`
+///   or `"SCREAMING_SNAKE_CASE"` instead of `"kebab-case"`.
+/// - `#[mpv(rename = "...")]` on a field or variant, to use a different name.
+/// - `#[mpv(default)]` on a field, to use [`Default::default()`] if the key is missing. [`Option`] fields always default to [`None`].
+/// - `#[mpv(skip)]` on a field, to never read or write it, using [`Default::default()`] instead.
+///
+/// # Example
+#[cfg_attr(feature = "derive", doc = "```")]
+#[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+///# use libmpv_client::*;
+/// #[derive(MpvFormat, MpvRecv, MpvSend, Debug, PartialEq)]
+/// enum Hwdec {
+///     No,
+///     Auto,
+///     AutoSafe,
+///     #[mpv(rename = "vaapi")]
+///     VaApi,
+/// }
+///
+/// #[derive(MpvFormat, MpvRecv, Debug)]
+/// struct Track {
+///     id: i64,
+///     #[mpv(rename = "type")]
+///     kind: String,
+///     title: Option<String>,
+///     #[mpv(default)]
+///     external: bool,
+/// }
+///
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// handle.set_property("hwdec", Hwdec::AutoSafe)?;
+/// let tracks: Vec<Track> = handle.get_property::<Node>("track-list").and_then(FromNode::from_node)?;
+///#     Ok(())
+///# }
+/// ```
+pub trait FromNode: Sized {
+    /// Convert `node` into `Self`, or fail with [`RustError::NodeConversion`].
+    fn from_node(node: Node) -> Result<Self>;
+
+    /// The value used when `Self` is a field whose key is missing from its [`Node::Map`], or [`None`] if that is an error.
+    ///
+    /// This is [`None`] by default, and `Some(None)` for [`Option`].
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// A type which can be converted into a [`Node`].
+///
+/// Types deriving this with `#[derive(MpvFormat, MpvSend)]` (which requires the `derive` feature) are also [`MpvSend`] through [`Format::NODE`].
+/// See [`FromNode`] for the supported attributes.
+pub trait ToNode {
+    /// Convert `self` into a [`Node`].
+    fn to_node(&self) -> Node;
+
+    /// Whether `self` is left out when it is a field of a [`Node::Map`].
+    ///
+    /// This is `false` by default, and `true` for [`Option::None`].
+    fn is_absent(&self) -> bool {
+        false
+    }
+}
+
+impl<T: private::DerivedFormat> MpvFormat for T {
+    const MPV_FORMAT: Format = Format::NODE;
+}
+
+impl<T: FromNode + private::DerivedRecv> MpvRecv for T {}
+impl<T: FromNode + private::DerivedRecv> MpvRecvInternal for T {
+    unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
+        T::from_node(unsafe { Node::from_ptr(ptr)? })
+    }
+
+    unsafe fn from_mpv<F: Fn(*mut c_void) -> Result<i32>>(fun: F) -> Result<Self> {
+        T::from_node(unsafe { Node::from_mpv(fun)? })
+    }
+}
+
+impl<T: ToNode + private::DerivedSend> MpvSend for T {}
+impl<T: ToNode + private::DerivedSend> MpvSendInternal for T {
+    fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+        self.to_node().to_mpv(fun)
+    }
+}
+
+/// Create a [`RustError::NodeConversion`] for a value at the root of the converted [`Node`].
+pub(crate) fn node_error(expected: &'static str, found: impl Into<String>) -> Error {
+    Error::Rust(RustError::NodeConversion(NodeError { path: String::new(), expected, found: found.into() }))
+}
+
+/// Prepend `segment` to the path of a [`RustError::NodeConversion`], leaving other errors untouched.
+pub(crate) fn error_at(error: Error, segment: &str) -> Error {
+    match error {
+        Error::Rust(RustError::NodeConversion(e)) => Error::Rust(RustError::NodeConversion(e.at(segment))),
+        e => e,
+    }
+}
+
+impl FromNode for Node {
+    fn from_node(node: Node) -> Result<Self> {
+        Ok(node)
+    }
+}
+
+impl ToNode for Node {
+    fn to_node(&self) -> Node {
+        self.clone()
+    }
+}
+
+impl FromNode for String {
+    fn from_node(node: Node) -> Result<Self> {
+        match node {
+            Node::String(s) => Ok(s),
+            other => Err(node_error("a string", other.type_name())),
+        }
+    }
+}
+
+impl ToNode for String {
+    fn to_node(&self) -> Node {
+        Node::String(self.clone())
+    }
+}
+
+impl FromNode for bool {
+    fn from_node(node: Node) -> Result<Self> {
+        match node {
+            Node::Flag(b) => Ok(b),
+            other => Err(node_error("a flag", other.type_name())),
+        }
+    }
+}
+
+impl ToNode for bool {
+    fn to_node(&self) -> Node {
+        Node::Flag(*self)
+    }
+}
+
+macro_rules! int_conversion {
+    (from: $($t:ty),*) => {$(
+        impl FromNode for $t {
+            fn from_node(node: Node) -> Result<Self> {
+                match node {
+                    Node::Int64(i) => <$t>::try_from(i).map_err(|_| node_error(concat!("an integer in the range of `", stringify!($t), "`"), i.to_string())),
+                    other => Err(node_error("an integer", other.type_name())),
+                }
+            }
+        }
+    )*};
+    ($($t:ty),*) => {$(
+        impl FromNode for $t {
+            fn from_node(node: Node) -> Result<Self> {
+                match node {
+                    Node::Int64(i) => <$t>::try_from(i).map_err(|_| node_error(concat!("an integer in the range of `", stringify!($t), "`"), i.to_string())),
+                    other => Err(node_error("an integer", other.type_name())),
+                }
+            }
+        }
+
+        impl ToNode for $t {
+            fn to_node(&self) -> Node {
+                Node::Int64(*self as i64)
+            }
+        }
+    )*};
+}
+
+int_conversion!(i8, i16, i32, i64, u8, u16, u32);
+// These may not fit into an `i64`, so they can't be infallibly converted into a `Node`.
+int_conversion!(from: u64, usize);
+
+impl FromNode for f64 {
+    fn from_node(node: Node) -> Result<Self> {
+        match node {
+            Node::Double(d) => Ok(d),
+            Node::Int64(i) => Ok(i as f64),
+            other => Err(node_error("a number", other.type_name())),
+        }
+    }
+}
+
+impl ToNode for f64 {
+    fn to_node(&self) -> Node {
+        Node::Double(*self)
+    }
+}
+
+impl FromNode for f32 {
+    fn from_node(node: Node) -> Result<Self> {
+        f64::from_node(node).and_then(|d| f32_checked(d).map_err(|_| node_error("a number in the range of `f32`", d.to_string())))
+    }
+}
+
+impl ToNode for f32 {
+    fn to_node(&self) -> Node {
+        Node::Double(f64::from(*self))
+    }
+}
+
+impl FromNode for Duration {
+    fn from_node(node: Node) -> Result<Self> {
+        f64::from_node(node).and_then(|d| duration_checked(d).map_err(|_| node_error("a non-negative number of seconds", d.to_string())))
+    }
+}
+
+impl ToNode for Duration {
+    fn to_node(&self) -> Node {
+        Node::Double(self.as_secs_f64())
+    }
+}
+
+impl<T: FromNode> FromNode for Option<T> {
+    fn from_node(node: Node) -> Result<Self> {
+        match node {
+            Node::None => Ok(None),
+            node => T::from_node(node).map(Some),
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: ToNode> ToNode for Option<T> {
+    fn to_node(&self) -> Node {
+        self.as_ref().map_or(Node::None, T::to_node)
+    }
+
+    fn is_absent(&self) -> bool {
+        self.is_none()
+    }
+}
+
+impl<T: FromNode> FromNode for Vec<T> {
+    fn from_node(node: Node) -> Result<Self> {
+        match node {
+            Node::Array(array) => array.into_iter().enumerate()
+                .map(|(i, node)| T::from_node(node).map_err(|e| error_at(e, &i.to_string())))
+                .collect(),
+            // mpv may provide raw data as a byte array, which can be read as any `Vec` of integers.
+            Node::ByteArray(bytes) => bytes.into_iter().enumerate()
+                .map(|(i, byte)| T::from_node(Node::Int64(byte as i64)).map_err(|e| error_at(e, &i.to_string())))
+                .collect(),
+            other => Err(node_error("an array", other.type_name())),
+        }
+    }
+}
+
+impl<T: ToNode> ToNode for Vec<T> {
+    fn to_node(&self) -> Node {
+        Node::Array(self.iter().map(T::to_node).collect())
+    }
+}
+
+impl<T: FromNode> FromNode for HashMap<String, T> {
+    fn from_node(node: Node) -> Result<Self> {
+        match node {
+            Node::Map(map) => map.into_iter()
+                .map(|(k, node)| match T::from_node(node) {
+                    Ok(v) => Ok((k, v)),
+                    Err(e) => Err(error_at(e, &k)),
+                })
+                .collect(),
+            other => Err(node_error("a map", other.type_name())),
+        }
+    }
+}
+
+impl<T: ToNode> ToNode for HashMap<String, T> {
+    fn to_node(&self) -> Node {
+        Node::Map(self.iter().map(|(k, v)| (k.clone(), v.to_node())).collect())
+    }
+}
+
+macro_rules! try_from_node {
+    ($($t:ty),*) => {$(
+        impl TryFrom<Node> for $t {
+            type Error = Error;
+
+            /// Convert with [`FromNode`], failing with [`RustError::NodeConversion`] if `node` has a different type.
+            fn try_from(node: Node) -> Result<Self> {
+                <$t>::from_node(node)
+            }
+        }
+    )*};
+}
+
+try_from_node!(String, bool, i64, f64);
+
+impl<T: MpvFormat> MpvFormat for Option<T> {
+    const MPV_FORMAT: Format = T::MPV_FORMAT;
+}
+
+/// Receiving an [`Option`] maps [`Error::PropertyUnavailable`] to [`None`], such as for `duration` while no file is loaded.
+impl<T: MpvRecv> MpvRecv for Option<T> {}
+impl<T: MpvRecv> MpvRecvInternal for Option<T> {
+    unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
+        unsafe { T::from_ptr(ptr) }.map(Some)
+    }
+
+    unsafe fn from_mpv<F: Fn(*mut c_void) -> Result<i32>>(fun: F) -> Result<Self> {
+        match unsafe { T::from_mpv(fun) } {
+            Err(Error::PropertyUnavailable) => Ok(None),
+            result => result.map(Some),
+        }
+    }
+}
+
+impl<T: private::Element> MpvFormat for Vec<T> {
+    const MPV_FORMAT: Format = Format::NODE;
+}
+
+/// A [`Vec`] is received through [`Format::NODE`] from a [`Node::Array`] (see [`FromNode`]).
+///
+/// [`Vec<Node>`](NodeArray) and [`Vec<u8>`](ByteArray) use their own formats instead.
+impl<T: FromNode + private::RecvElement> MpvRecv for Vec<T> {}
+impl<T: FromNode + private::RecvElement> MpvRecvInternal for Vec<T> {
+    unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
+        Self::from_node(unsafe { Node::from_ptr(ptr)? })
+    }
+
+    unsafe fn from_mpv<F: Fn(*mut c_void) -> Result<i32>>(fun: F) -> Result<Self> {
+        Self::from_node(unsafe { Node::from_mpv(fun)? })
+    }
+}
+
+/// A [`Vec`] is sent through [`Format::NODE`] as a [`Node::Array`] (see [`ToNode`]).
+///
+/// [`Vec<Node>`](NodeArray) and [`Vec<u8>`](ByteArray) use their own formats instead.
+impl<T: ToNode + private::SendElement> MpvSend for Vec<T> {}
+impl<T: ToNode + private::SendElement> MpvSendInternal for Vec<T> {
+    fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+        self.to_node().to_mpv(fun)
+    }
+}
+
+impl<T: private::Element> MpvFormat for HashMap<String, T> {
+    const MPV_FORMAT: Format = Format::NODE;
+}
+
+/// A [`HashMap`] is received through [`Format::NODE`] from a [`Node::Map`] (see [`FromNode`]).
+impl<T: FromNode + private::RecvElement> MpvRecv for HashMap<String, T> {}
+impl<T: FromNode + private::RecvElement> MpvRecvInternal for HashMap<String, T> {
+    unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
+        Self::from_node(unsafe { Node::from_ptr(ptr)? })
+    }
+
+    unsafe fn from_mpv<F: Fn(*mut c_void) -> Result<i32>>(fun: F) -> Result<Self> {
+        Self::from_node(unsafe { Node::from_mpv(fun)? })
+    }
+}
+
+/// A [`HashMap`] is sent through [`Format::NODE`] as a [`Node::Map`] (see [`ToNode`]).
+impl<T: ToNode + private::SendElement> MpvSend for HashMap<String, T> {}
+impl<T: ToNode + private::SendElement> MpvSendInternal for HashMap<String, T> {
+    fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+        self.to_node().to_mpv(fun)
+    }
+}
+
+/// Items used by the code generated by `#[derive(MpvFormat, MpvRecv, MpvSend)]`. Not public API.
+#[doc(hidden)]
+pub mod private {
+    use super::*;
+
+    /// Marks a type deriving `MpvFormat`, which makes it [`MpvFormat`] through [`Format::NODE`].
+    #[diagnostic::on_unimplemented(
+        message = "`{Self}` must also derive `MpvFormat`",
+        label = "missing `#[derive(MpvFormat)]`",
+    )]
+    pub trait DerivedFormat {}
+
+    /// Marks a type deriving `MpvRecv`, which makes it [`MpvRecv`] through [`FromNode`].
+    pub trait DerivedRecv: DerivedFormat {}
+
+    /// Marks a type deriving `MpvSend`, which makes it [`MpvSend`] through [`ToNode`].
+    pub trait DerivedSend: DerivedFormat {}
+
+    /// Marks a type which is converted through [`Format::NODE`] as the element of a [`Vec`] or [`HashMap`].
+    ///
+    /// [`Node`] and [`u8`] are excluded, because [`NodeArray`], [`NodeMap`] and [`ByteArray`] have their own formats.
+    pub trait Element {}
+
+    /// Marks a type which can be received as the element of a [`Vec`] or [`HashMap`] through [`Format::NODE`].
+    pub trait RecvElement: Element {}
+
+    /// Marks a type which can be sent as the element of a [`Vec`] or [`HashMap`] through [`Format::NODE`].
+    pub trait SendElement: Element {}
+
+    impl<T: DerivedFormat> Element for T {}
+    impl<T: DerivedRecv> RecvElement for T {}
+    impl<T: DerivedSend> SendElement for T {}
+
+    macro_rules! element {
+        (recv: $($t:ty),*) => {$( impl Element for $t {} impl RecvElement for $t {} )*};
+        ($($t:ty),*) => {$( impl Element for $t {} impl RecvElement for $t {} impl SendElement for $t {} )*};
+    }
+
+    element!(String, OsString, PathBuf, bool, i8, i16, i32, i64, u16, u32, f32, f64, Duration);
+    element!(recv: u64, usize);
+
+    impl<T: Element> Element for Option<T> {}
+    impl<T: Element> Element for Vec<T> {}
+    impl<T: Element> Element for HashMap<String, T> {}
+    impl<T: RecvElement> RecvElement for Option<T> {}
+    impl<T: SendElement> SendElement for Option<T> {}
+    impl<T: RecvElement> RecvElement for Vec<T> {}
+    impl<T: SendElement> SendElement for Vec<T> {}
+    impl<T: RecvElement> RecvElement for HashMap<String, T> {}
+    impl<T: SendElement> SendElement for HashMap<String, T> {}
+
+    pub fn expect_map(node: Node) -> Result<NodeMap> {
+        match node {
+            Node::Map(map) => Ok(map),
+            other => Err(node_error("a map", other.type_name())),
+        }
+    }
+
+    pub fn expect_array(node: Node, len: usize, expected: &'static str) -> Result<std::vec::IntoIter<Node>> {
+        match node {
+            Node::Array(array) if array.len() == len => Ok(array.into_iter()),
+            Node::Array(array) => Err(node_error(expected, format!("an array of length {}", array.len()))),
+            other => Err(node_error(expected, other.type_name())),
+        }
+    }
+
+    pub fn expect_variant(node: Node, expected: &'static str) -> Result<String> {
+        match node {
+            Node::String(s) => Ok(s),
+            Node::Flag(true) => Ok("yes".to_string()),
+            Node::Flag(false) => Ok("no".to_string()),
+            other => Err(node_error(expected, other.type_name())),
+        }
+    }
+
+    pub fn unknown_variant(found: &str, expected: &'static str) -> Error {
+        node_error(expected, format!("`{found}`"))
+    }
+
+    pub fn element<T: FromNode>(elements: &mut std::vec::IntoIter<Node>, index: usize) -> Result<T> {
+        let node = elements.next().unwrap_or(Node::None);
+        T::from_node(node).map_err(|e| error_at(e, &index.to_string()))
+    }
+
+    pub fn field<T: FromNode>(map: &mut NodeMap, key: &str) -> Result<T> {
+        match node_map::take(map, key) {
+            Some(node) => T::from_node(node).map_err(|e| error_at(e, key)),
+            None => T::from_missing().ok_or_else(|| error_at(node_error("a value", "nothing"), key)),
+        }
+    }
+
+    pub fn field_or_default<T: FromNode + Default>(map: &mut NodeMap, key: &str) -> Result<T> {
+        match node_map::take(map, key) {
+            Some(node) => T::from_node(node).map_err(|e| error_at(e, key)),
+            None => Ok(T::default()),
+        }
+    }
+
+    pub fn insert_field<T: ToNode>(map: &mut NodeMap, key: &str, value: &T) {
+        if !value.is_absent() {
+            map.insert(key.to_string(), value.to_node());
+        }
+    }
+}
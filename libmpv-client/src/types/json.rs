@@ -0,0 +1,372 @@
+//! A JSON writer and parser for [`Node`]s, following the dialect of mpv's own `misc/json.c`.
+//!
+//! This is the same dialect used by the `utils.format_json()` and `utils.parse_json()` functions available to mpv's Lua scripts.
+//!
+//! Notable differences from strict JSON:
+//! - Doubles are always written with 6 decimal places (`%f`), and non-finite doubles are written as strings (e.g. `"inf"`).
+//! - Numbers without a fraction or exponent are parsed as [`Node::Int64`], all others as [`Node::Double`].
+//! - Arrays and objects may have a trailing `,`.
+//! - Objects accept `=` in addition to `:`, and keys may be unquoted if they start with a letter or `_`, followed by letters, digits and `_`.
+//! - Strings accept the escapes `\e` and `\'`, and `\xAB` for a single byte. Strings which are not valid UTF-8 after unescaping
+//!   are parsed as [`Node::RawString`], which is written back with `\x` escapes for its invalid bytes.
+//!
+//! Unlike mpv, a `\u` escape of a UTF-16 high surrogate is combined with a following low surrogate into a single character,
+//! and a lone surrogate is rejected.
+
+use std::fmt::Write;
+use crate::*;
+use crate::error::{JsonError, RustError};
+
+/// mpv refuses to parse JSON nested deeper than this.
+const MAX_DEPTH: usize = 50;
+
+fn json_error(message: &'static str, position: usize) -> Error {
+    Error::Rust(RustError::Json(JsonError { message, position }))
+}
+
+/// Write a [`Node`] as compact JSON.
+///
+/// [`Node::ByteArray`] and [`Node::Unknown`] cannot be represented in mpv's JSON dialect and are rejected with [`RustError::Json`].
+/// The invalid bytes of a [`Node::RawString`] are written as `\xAB` escapes.
+pub(crate) fn write(node: &Node) -> Result<String> {
+    let mut out = String::new();
+    write_node(&mut out, node)?;
+    Ok(out)
+}
+
+fn write_node(out: &mut String, node: &Node) -> Result<()> {
+    match node {
+        Node::None => out.push_str("null"),
+        Node::String(s) => write_str(out, s),
+        Node::RawString(bytes) => write_raw_str(out, bytes),
+        Node::Flag(b) => out.push_str(if *b { "true" } else { "false" }),
+        Node::Int64(i) => { let _ = write!(out, "{i}"); }
+        Node::Double(d) if d.is_finite() => { let _ = write!(out, "{d:.6}"); }
+        Node::Double(d) if d.is_nan() => out.push_str("\"nan\""),
+        Node::Double(d) => out.push_str(if d.is_sign_negative() { "\"-inf\"" } else { "\"inf\"" }),
+        Node::Array(array) => {
+            out.push('[');
+            for (i, value) in array.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_node(out, value)?;
+            }
+            out.push(']');
+        }
+        Node::Map(map) => {
+            out.push('{');
+            for (i, (key, value)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_str(out, key);
+                out.push(':');
+                write_node(out, value)?;
+            }
+            out.push('}');
+        }
+        Node::ByteArray(_) => return Err(json_error("byte arrays cannot be written as JSON", out.len())),
+        Node::Unknown(_) => return Err(json_error("nodes of unknown format cannot be written as JSON", out.len())),
+    }
+
+    Ok(())
+}
+
+fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    write_escaped(out, s);
+    out.push('"');
+}
+
+fn write_raw_str(out: &mut String, bytes: &[u8]) {
+    out.push('"');
+    for chunk in bytes.utf8_chunks() {
+        write_escaped(out, chunk.valid());
+        for byte in chunk.invalid() {
+            let _ = write!(out, "\\x{byte:02x}");
+        }
+    }
+    out.push('"');
+}
+
+fn write_escaped(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0C}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+}
+
+/// Parse a JSON document into a [`Node`].
+///
+/// Trailing whitespace is allowed, but any other trailing data is rejected.
+pub(crate) fn parse(text: &str) -> Result<Node> {
+    let mut parser = Parser { text: text.as_bytes(), pos: 0 };
+
+    let node = parser.value(0)?;
+    parser.skip_whitespace();
+
+    if parser.pos != parser.text.len() {
+        return Err(json_error("trailing data after JSON value", parser.pos));
+    }
+
+    Ok(node)
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn error<T>(&self, message: &'static str) -> Result<T> {
+        Err(json_error(message, self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\r' | b'\n') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Node> {
+        if depth > MAX_DEPTH {
+            return self.error("JSON nested too deeply");
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => self.string().map(|bytes| match String::from_utf8(bytes) {
+                Ok(s) => Node::String(s),
+                Err(e) => Node::RawString(e.into_bytes()),
+            }),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => {
+                for (literal, node) in [("null", Node::None), ("true", Node::Flag(true)), ("false", Node::Flag(false))] {
+                    if self.text[self.pos..].starts_with(literal.as_bytes()) {
+                        self.pos += literal.len();
+                        return Ok(node);
+                    }
+                }
+                self.error("unexpected character")
+            }
+            None => self.error("unexpected end of JSON"),
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Node> {
+        self.pos += 1; // [
+        let mut array = NodeArray::new();
+
+        loop {
+            if self.eat(b']') {
+                return Ok(Node::Array(array));
+            }
+
+            array.push(self.value(depth + 1)?);
+
+            if !self.eat(b',') && !self.eat(b']') {
+                return self.error("expected ',' or ']'");
+            } else if self.text[self.pos - 1] == b']' {
+                return Ok(Node::Array(array));
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Node> {
+        self.pos += 1; // {
+        let mut map = NodeMap::new();
+
+        loop {
+            if self.eat(b'}') {
+                return Ok(Node::Map(map));
+            }
+
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some(b'"') => String::from_utf8(self.string()?).map_err(|e| Error::from(e.utf8_error()))?,
+                _ => self.identifier()?,
+            };
+
+            if !self.eat(b':') && !self.eat(b'=') {
+                return self.error("expected ':' or '='");
+            }
+
+            let value = self.value(depth + 1)?;
+            map.insert(key, value);
+
+            if !self.eat(b',') && !self.eat(b'}') {
+                return self.error("expected ',' or '}'");
+            } else if self.text[self.pos - 1] == b'}' {
+                return Ok(Node::Map(map));
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String> {
+        let start = self.pos;
+        if !matches!(self.peek(), Some(b'a'..=b'z' | b'A'..=b'Z' | b'_')) {
+            return self.error("expected object key");
+        }
+
+        while let Some(b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_') = self.peek() {
+            self.pos += 1;
+        }
+
+        // The identifier consists only of ASCII characters, so this can't fail.
+        Ok(std::str::from_utf8(&self.text[start..self.pos]).unwrap_or_default().to_string())
+    }
+
+    /// Parse a string into its raw bytes, which may not be valid UTF-8 because of `\x` escapes.
+    fn string(&mut self) -> Result<Vec<u8>> {
+        self.pos += 1; // "
+        let mut bytes = Vec::new();
+
+        loop {
+            match self.peek() {
+                None => return self.error("unterminated string"),
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'\'') => '\'',
+                        Some(b'b') => '\u{08}',
+                        Some(b'e') => '\u{1B}',
+                        Some(b'f') => '\u{0C}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        Some(b'x') => {
+                            bytes.push(self.hex(2)? as u8);
+                            self.pos += 1;
+                            continue;
+                        }
+                        _ => return self.error("invalid escape sequence"),
+                    };
+                    self.pos += 1;
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(c) => {
+                    bytes.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Parse the `XXXX` of a `\uXXXX` escape (and a following low surrogate, if needed), leaving `pos` on its last digit.
+    fn unicode_escape(&mut self) -> Result<char> {
+        let high = self.hex(4)?;
+
+        let code = if (0xD800..0xDC00).contains(&high) && self.text[self.pos + 1..].starts_with(b"\\u") {
+            self.pos += 2;
+            let low = self.hex(4)?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return self.error("invalid UTF-16 surrogate pair");
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+
+        match char::from_u32(code) {
+            Some(c) => Ok(c),
+            None => self.error("invalid unicode escape"),
+        }
+    }
+
+    /// Parse the `len` hex digits following `pos`, leaving `pos` on the last one.
+    fn hex(&mut self, len: usize) -> Result<u32> {
+        let Some(digits) = self.text.get(self.pos + 1..self.pos + 1 + len).filter(|digits| digits.iter().all(u8::is_ascii_hexdigit)) else {
+            return self.error("invalid hex escape");
+        };
+
+        let code = digits.iter().fold(0, |code, &digit| code << 4 | (digit as char).to_digit(16).unwrap_or_default());
+        self.pos += len;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Node> {
+        let start = self.pos;
+        let mut is_double = false;
+
+        while let Some(c) = self.peek() {
+            match c {
+                b'0'..=b'9' | b'-' | b'+' => {}
+                b'.' | b'e' | b'E' => is_double = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+
+        // The number consists only of ASCII characters, so this can't fail.
+        let number = std::str::from_utf8(&self.text[start..self.pos]).unwrap_or_default();
+
+        if !is_double && let Ok(i) = number.parse::<i64>() {
+            return Ok(Node::Int64(i));
+        }
+
+        match number.parse::<f64>() {
+            Ok(d) => Ok(Node::Double(d)),
+            Err(_) => {
+                self.pos = start;
+                self.error("invalid number")
+            }
+        }
+    }
+}
+
+impl Node {
+    /// Write this [`Node`] as compact JSON, in mpv's own dialect (the same as `utils.format_json()` in Lua scripts).
+    ///
+    /// Doubles are always written with 6 decimal places, and non-finite doubles are written as the strings `"inf"`, `"-inf"` and `"nan"`.
+    /// [`Node::ByteArray`] and [`Node::Unknown`] cannot be represented and are rejected with [`RustError::Json`].
+    /// The invalid bytes of a [`Node::RawString`] are written as `\xAB` escapes, which mpv and [`Node::from_json_str()`] parse back.
+    pub fn to_json_string(&self) -> Result<String> {
+        write(self)
+    }
+
+    /// Parse JSON in mpv's own dialect (the same as `utils.parse_json()` in Lua scripts).
+    ///
+    /// Numbers without a fraction or exponent become [`Node::Int64`], all others [`Node::Double`].
+    /// In addition to strict JSON, trailing commas, `=` between keys and values, unquoted identifier keys,
+    /// and the escapes `\e`, `\'` and `\xAB` are accepted.
+    /// A string which is not valid UTF-8 after unescaping becomes a [`Node::RawString`]. Invalid input is rejected with [`RustError::Json`].
+    pub fn from_json_str(text: &str) -> Result<Node> {
+        parse(text)
+    }
+}
@@ -0,0 +1,99 @@
+//! Conversions between [`Node`] and [`serde_json::Value`], enabled with the `json` feature.
+
+use serde_json::{Map, Number, Value};
+use crate::*;
+use crate::types::convert::node_error;
+
+/// How a [`Node::ByteArray`] is represented in a [`Value`], which has no binary type. Requires the `json` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteArrayEncoding {
+    /// An array of numbers from `0` to `255`, which converts back into a [`Node::Array`] of [`Node::Int64`]s.
+    #[default]
+    Array,
+    /// A string in standard, padded base64, which converts back into a [`Node::String`].
+    Base64,
+}
+
+impl Node {
+    /// Convert this [`Node`] into a [`Value`], representing [`Node::ByteArray`] as `byte_arrays`. Requires the `json` feature.
+    ///
+    /// [`Node::Int64`] becomes an integer and [`Node::Double`] a floating-point [`Number`], so the two stay apart when converted back.
+    /// Non-finite doubles, which JSON cannot represent, become the strings `"inf"`, `"-inf"` and `"nan"`, like in [`Node::to_json_string()`].
+    /// [`Node::RawString`] is converted lossily, and [`Node::Unknown`] becomes [`Value::Null`].
+    ///
+    /// [`From<Node>`] for [`Value`] uses [`ByteArrayEncoding::Array`].
+    pub fn to_json_value(&self, byte_arrays: ByteArrayEncoding) -> Value {
+        match self {
+            Node::None => Value::Null,
+            Node::String(s) => Value::String(s.clone()),
+            Node::RawString(bytes) => Value::String(String::from_utf8_lossy(bytes).into_owned()),
+            Node::Flag(b) => Value::Bool(*b),
+            Node::Int64(i) => Value::Number((*i).into()),
+            Node::Double(d) => match Number::from_f64(*d) {
+                Some(n) => Value::Number(n),
+                None if d.is_nan() => Value::String("nan".to_string()),
+                None => Value::String(if d.is_sign_negative() { "-inf" } else { "inf" }.to_string()),
+            },
+            Node::Array(array) => Value::Array(array.iter().map(|node| node.to_json_value(byte_arrays)).collect()),
+            Node::Map(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), v.to_json_value(byte_arrays))).collect::<Map<_, _>>()),
+            Node::ByteArray(bytes) => match byte_arrays {
+                ByteArrayEncoding::Array => Value::Array(bytes.iter().map(|b| Value::Number((*b).into())).collect()),
+                ByteArrayEncoding::Base64 => Value::String(base64(bytes)),
+            },
+            Node::Unknown(_) => Value::Null,
+        }
+    }
+}
+
+impl From<&Node> for Value {
+    fn from(node: &Node) -> Self {
+        node.to_json_value(ByteArrayEncoding::default())
+    }
+}
+
+impl From<Node> for Value {
+    fn from(node: Node) -> Self {
+        Value::from(&node)
+    }
+}
+
+impl TryFrom<Value> for Node {
+    type Error = Error;
+
+    /// Convert a [`Value`] into a [`Node`]. Requires the `json` feature.
+    ///
+    /// Integers become [`Node::Int64`] and all other numbers [`Node::Double`].
+    /// Integers above [`i64::MAX`] are rejected with [`RustError::NodeConversion`](crate::error::RustError::NodeConversion).
+    fn try_from(value: Value) -> Result<Self> {
+        Ok(match value {
+            Value::Null => Node::None,
+            Value::Bool(b) => Node::Flag(b),
+            Value::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+                (Some(i), _, _) => Node::Int64(i),
+                (None, Some(u), _) => return Err(node_error("an integer in the range of `i64`", u.to_string())),
+                (None, None, d) => Node::Double(d.unwrap_or(f64::NAN)),
+            },
+            Value::String(s) => Node::String(s),
+            Value::Array(array) => Node::Array(array.into_iter().map(Node::try_from).collect::<Result<_>>()?),
+            Value::Object(map) => Node::Map(map.into_iter().map(|(k, v)| Ok((k, Node::try_from(v)?))).collect::<Result<_>>()?),
+        })
+    }
+}
+
+/// Encode `bytes` as standard, padded base64.
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
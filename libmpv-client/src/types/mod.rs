@@ -5,6 +5,14 @@ pub(crate) mod node_array;
 pub(crate) mod node_map;
 pub(crate) mod byte_array;
 pub(crate) mod traits;
+pub(crate) mod json;
+pub(crate) mod convert;
+pub(crate) mod os_string;
+pub(crate) mod node_ref;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "json")]
+mod json_value;
 mod tests;
 
 pub use node::Node;
@@ -12,6 +20,12 @@ pub use node_array::NodeArray;
 pub use node_map::NodeMap;
 pub use byte_array::ByteArray;
 pub use basics::OsdString;
+pub use convert::{FromNode, ToNode};
+pub use node_ref::{NodeGuard, NodeRef};
+#[cfg(feature = "serde")]
+pub use self::serde::{to_node, from_node};
+#[cfg(feature = "json")]
+pub use json_value::ByteArrayEncoding;
 
 pub use traits::MpvFormat;
 pub use traits::MpvSend;
@@ -38,7 +52,9 @@ impl Format {
     /// Although the encoding is usually UTF-8, this is not always the case. File tags often store strings in some legacy codepage,
     /// and even filenames don't necessarily have to be in UTF-8 (at least on Linux).
     /// 
-    /// If this crate receives invalid UTF-8, a [`RustError::InvalidUtf8`](crate::error::RustError::InvalidUtf8) may be returned.
+    /// If this crate receives invalid UTF-8 as a [`String`], a [`RustError::InvalidUtf8`](crate::error::RustError::InvalidUtf8) is returned.
+    /// To handle such strings losslessly, receive them as [`OsString`](std::ffi::OsString) or [`PathBuf`](std::path::PathBuf) instead,
+    /// which (on Unix) keep the raw bytes. Within a [`Node`], they are kept as a [`Node::RawString`].
     ///
     /// On Windows, filenames are always UTF-8, and libmpv converts between UTF-8 and UTF-16 when using Win32 API functions.
     ///
@@ -69,7 +85,8 @@ impl Format {
     /// A [`Format`] representing the crate's [`NodeArray`] (a type alias for [`Vec<Node>`])
     /// and mpv's [`MPV_FORMAT_NODE_ARRAY`](libmpv_client_sys::mpv_format_MPV_FORMAT_NODE_ARRAY).
     pub const NODE_ARRAY: Format = Format(libmpv_client_sys::mpv_format_MPV_FORMAT_NODE_ARRAY);
-    /// A [`Format`] representing the crate's [`NodeMap`] (a type alias for [`HashMap<String, Node>`](std::collections::HashMap))
+    /// A [`Format`] representing the crate's [`NodeMap`] (a type alias for [`HashMap<String, Node>`](std::collections::HashMap),
+    /// or for an `IndexMap<String, Node>` with the `preserve-order` feature)
     /// and mpv's [`MPV_FORMAT_NODE_MAP`](libmpv_client_sys::mpv_format_MPV_FORMAT_NODE_MAP).
     pub const NODE_MAP: Format = Format(libmpv_client_sys::mpv_format_MPV_FORMAT_NODE_MAP);
     /// A [`Format`] representing the crate's [`ByteArray`] (a type alias for [`Vec<u8>`])
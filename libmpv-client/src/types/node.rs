@@ -1,12 +1,14 @@
 #![allow(non_upper_case_globals)]
 
-use std::collections::HashMap;
 use std::ffi::{c_void, CStr, CString, c_char};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
+use std::ops::Index;
 use libmpv_client_sys::{mpv_byte_array, mpv_format_MPV_FORMAT_BYTE_ARRAY, mpv_format_MPV_FORMAT_DOUBLE, mpv_format_MPV_FORMAT_FLAG, mpv_format_MPV_FORMAT_INT64, mpv_format_MPV_FORMAT_NODE_ARRAY, mpv_format_MPV_FORMAT_NODE_MAP, mpv_format_MPV_FORMAT_NONE, mpv_format_MPV_FORMAT_STRING, mpv_node, mpv_node__bindgen_ty_1, mpv_node_list};
 use crate::*;
+use crate::error::{NodeError, RustError};
+use crate::types::convert::error_at;
 use crate::byte_array::MpvByteArray;
 use crate::node_array::MpvNodeArray;
 use crate::node_map::MpvNodeMap;
@@ -14,6 +16,7 @@ use crate::types::traits::{MpvFormat, MpvRecv, MpvRecvInternal, MpvRepr, MpvSend
 
 /// Generic data storage for various mpv argument types and responses.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Node {
     /// The [`Node`] is empty. See [`Format::NONE`].
     None,
@@ -31,6 +34,15 @@ pub enum Node {
     Map(NodeMap),
     /// The [`Node`] contains a raw, untyped byte array. See [`Format::BYTE_ARRAY`].
     ByteArray(ByteArray),
+    /// The [`Node`] contains a string which is not valid UTF-8, as its raw bytes. See [`Format::STRING`].
+    ///
+    /// mpv strings are usually UTF-8, but file tags and filenames (at least on Linux) don't have to be.
+    /// Strings received from mpv only use this variant if they are not valid UTF-8, and it is sent back to mpv as the same bytes.
+    RawString(Vec<u8>),
+    /// The [`Node`] was received from mpv with a format this crate doesn't know about yet, which is kept as its raw value.
+    ///
+    /// This can only happen with a newer version of mpv. Sending it back to mpv fails with [`RustError::UnknownNodeFormat`].
+    Unknown(libmpv_client_sys::mpv_format),
 }
 
 #[derive(Debug)]
@@ -77,7 +89,7 @@ impl MpvRecvInternal for Node {
 impl MpvSend for Node {}
 impl MpvSendInternal for Node {
     fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
-        let repr = self.to_mpv_repr();
+        let repr = self.to_mpv_repr()?;
 
         fun(repr.ptr() as *mut c_void)
     }
@@ -86,7 +98,7 @@ impl MpvSendInternal for Node {
 impl ToMpvRepr for Node {
     type ReprWrap<'a> = MpvNode<'a>;
 
-    fn to_mpv_repr(&self) -> Self::ReprWrap<'_> {
+    fn to_mpv_repr(&self) -> Result<Self::ReprWrap<'_>> {
         let mut repr = MpvNode {
             _original: PhantomData,
             _owned_cstring: None,
@@ -102,8 +114,7 @@ impl ToMpvRepr for Node {
                 repr.node.format = mpv_format_MPV_FORMAT_NONE;
             },
             Node::String(x) => {
-                // TODO: Remove this unwrap() by converting to_mpv_repr to return Result<>. See traits.rs.
-                repr._owned_cstring = Some(CString::new(x.as_bytes()).unwrap_or_default());
+                repr._owned_cstring = Some(CString::new(x.as_bytes())?);
                 let cstring_ptr = repr._owned_cstring.as_ref().unwrap().as_ptr(); // SAFETY: We just assigned Some.
 
                 repr.node.u = mpv_node__bindgen_ty_1 { string: cstring_ptr as *mut c_char };
@@ -122,29 +133,37 @@ impl ToMpvRepr for Node {
                 repr.node.format = mpv_format_MPV_FORMAT_DOUBLE;
             }
             Node::Array(x) => {
-                repr._array_repr = Some(x.to_mpv_repr());
+                repr._array_repr = Some(x.to_mpv_repr()?);
                 let mpv_ptr = repr._array_repr.as_ref().unwrap().ptr(); // SAFETY: We just assigned Some.
 
                 repr.node.u = mpv_node__bindgen_ty_1 { list: mpv_ptr as *mut mpv_node_list };
                 repr.node.format = mpv_format_MPV_FORMAT_NODE_ARRAY;
             }
             Node::Map(x) => {
-                repr._map_repr = Some(x.to_mpv_repr());
+                repr._map_repr = Some(x.to_mpv_repr()?);
                 let mpv_ptr = repr._map_repr.as_ref().unwrap().ptr(); // SAFETY: We just assigned Some.
 
                 repr.node.u = mpv_node__bindgen_ty_1 { list: mpv_ptr as *mut mpv_node_list };
                 repr.node.format = mpv_format_MPV_FORMAT_NODE_MAP;
             }
+            Node::RawString(x) => {
+                repr._owned_cstring = Some(CString::new(x.as_slice())?);
+                let cstring_ptr = repr._owned_cstring.as_ref().unwrap().as_ptr(); // SAFETY: We just assigned Some.
+
+                repr.node.u = mpv_node__bindgen_ty_1 { string: cstring_ptr as *mut c_char };
+                repr.node.format = mpv_format_MPV_FORMAT_STRING;
+            }
             Node::ByteArray(x) => {
-                repr._bytes_repr = Some(x.to_mpv_repr());
+                repr._bytes_repr = Some(x.to_mpv_repr()?);
                 let mpv_ptr = repr._bytes_repr.as_ref().unwrap().ptr(); // SAFETY: We just assigned Some.
 
                 repr.node.u = mpv_node__bindgen_ty_1 { ba: mpv_ptr as *mut mpv_byte_array };
                 repr.node.format = mpv_format_MPV_FORMAT_BYTE_ARRAY;
             }
+            Node::Unknown(format) => return Err(Error::Rust(RustError::UnknownNodeFormat(*format))),
         };
 
-        repr
+        Ok(repr)
     }
 }
 
@@ -155,14 +174,35 @@ impl Node {
 
         match node.format {
             mpv_format_MPV_FORMAT_NONE => Ok(Node::None),
-            mpv_format_MPV_FORMAT_STRING => Ok(Node::String(unsafe { CStr::from_ptr(node.u.string) }.to_str()?.to_string())),
+            mpv_format_MPV_FORMAT_STRING => {
+                match String::from_utf8(unsafe { CStr::from_ptr(node.u.string) }.to_bytes().to_vec()) {
+                    Ok(s) => Ok(Node::String(s)),
+                    Err(e) => Ok(Node::RawString(e.into_bytes())),
+                }
+            }
             mpv_format_MPV_FORMAT_FLAG => Ok(Node::Flag(unsafe { node.u.flag } != 0)),
             mpv_format_MPV_FORMAT_INT64 => Ok(Node::Int64(unsafe { node.u.int64 })),
             mpv_format_MPV_FORMAT_DOUBLE => Ok(Node::Double(unsafe { node.u.double_ })),
             mpv_format_MPV_FORMAT_NODE_ARRAY => Ok(Node::Array(unsafe { NodeArray::from_ptr(node.u.list as *const c_void)? })),
             mpv_format_MPV_FORMAT_NODE_MAP => Ok(Node::Map(unsafe { NodeMap::from_ptr(node.u.list as *const c_void)? })),
             mpv_format_MPV_FORMAT_BYTE_ARRAY => Ok(Node::ByteArray(unsafe { ByteArray::from_ptr(node.u.ba as *const c_void)? })),
-            _ => unimplemented!()
+            format => Ok(Node::Unknown(format)),
+        }
+    }
+
+    /// A short, human-readable name of this [`Node`]'s variant, used in error messages.
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Node::None => "none",
+            Node::String(_) => "a string",
+            Node::Flag(_) => "a flag",
+            Node::Int64(_) => "an integer",
+            Node::Double(_) => "a double",
+            Node::Array(_) => "an array",
+            Node::Map(_) => "a map",
+            Node::ByteArray(_) => "a byte array",
+            Node::RawString(_) => "a non-UTF-8 string",
+            Node::Unknown(_) => "a value of unknown format",
         }
     }
 }
@@ -170,9 +210,9 @@ impl Node {
 impl From<&[(&str, Node)]> for Node {
     /// Convenience function to create a [`Node::Map`] from a [`&[(&str, Node)]`] slice.
     ///
-    /// This creates the underlying [`HashMap`] and clones the references [`Node`]s.
+    /// This creates the underlying [`NodeMap`] and clones the references [`Node`]s.
     fn from(slice: &[(&str, Node)]) -> Self {
-        let map: HashMap<String, Node> = slice.iter()
+        let map: NodeMap = slice.iter()
             .map(|(k, v)| (k.to_string(), v.clone()))
             .collect();
         Node::Map(map)
@@ -186,4 +226,170 @@ impl From<&[Node]> for Node {
     fn from(slice: &[Node]) -> Self {
         Node::Array(slice.to_vec())
     }
-}
\ No newline at end of file
+}
+
+/// Returned by [`Index`] for missing entries.
+static NONE: Node = Node::None;
+
+impl Node {
+    /// The contents of a [`Node::String`].
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Node::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The contents of a [`Node::Flag`].
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Node::Flag(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The contents of a [`Node::Int64`].
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Node::Int64(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The contents of a [`Node::Double`], or of a [`Node::Int64`] converted to [`f64`].
+    ///
+    /// mpv often returns whole numbers as integers even for properties which are usually fractional.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Node::Double(d) => Some(*d),
+            Node::Int64(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    /// The contents of a [`Node::Array`].
+    pub fn as_array(&self) -> Option<&NodeArray> {
+        match self {
+            Node::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// The contents of a [`Node::Map`].
+    pub fn as_map(&self) -> Option<&NodeMap> {
+        match self {
+            Node::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// The contents of a [`Node::ByteArray`].
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Node::ByteArray(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Whether this is [`Node::None`].
+    pub fn is_none(&self) -> bool {
+        matches!(self, Node::None)
+    }
+
+    /// Look up a nested value by a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) style `path`, such as `/track-list/0/lang`.
+    ///
+    /// Each `/`-separated segment is a key of a [`Node::Map`] or an index into a [`Node::Array`].
+    /// Within a segment, `~1` stands for `/` and `~0` for `~`. The empty path refers to the [`Node`] itself.
+    ///
+    /// If the lookup fails, the returned [`RustError::NodeConversion`] has the path of the value which was missing or had the wrong type.
+    ///
+    /// # Example
+    /// ```
+    ///# use libmpv_client::*;
+    /// let tracks = node_map! {
+    ///     ("track-list", node_array!(node_map! { ("id", 1), ("lang", "eng") })),
+    /// };
+    ///
+    /// assert_eq!(tracks.pointer("/track-list/0/lang")?.as_str(), Some("eng"));
+    /// assert!(tracks.pointer("/track-list/1/lang").is_err());
+    ///# Ok::<(), Error>(())
+    /// ```
+    pub fn pointer(&self, path: &str) -> Result<&Node> {
+        let error = |path: &str, expected, found: &str| {
+            Error::Rust(RustError::NodeConversion(NodeError { path: path.to_string(), expected, found: found.to_string() }))
+        };
+
+        if path.is_empty() {
+            return Ok(self);
+        }
+        let Some(segments) = path.strip_prefix('/') else {
+            return Err(error("", "a path starting with `/`", &format!("`{path}`")));
+        };
+
+        let mut node = self;
+        let mut walked = 0;
+        for raw_segment in segments.split('/') {
+            let parent = &path[..walked];
+            walked += raw_segment.len() + 1;
+            let segment = raw_segment.replace("~1", "/").replace("~0", "~");
+
+            node = match node {
+                Node::Map(map) => map.get(&segment),
+                Node::Array(array) => match segment.parse::<usize>() {
+                    Ok(index) => array.get(index),
+                    Err(_) => return Err(error(&path[..walked], "an array index", &format!("`{segment}`"))),
+                },
+                other => return Err(error(parent, "a map or an array", other.type_name())),
+            }.ok_or_else(|| error(&path[..walked], "a value", "nothing"))?;
+        }
+
+        Ok(node)
+    }
+
+    /// Look up a nested value like [`Node::pointer()`], and convert a copy of it with [`FromNode`].
+    ///
+    /// Conversion errors report their path from the root of `self`.
+    /// A missing value is an error even if `T` is an [`Option`], since the lookup fails before the conversion.
+    ///
+    /// # Example
+    /// ```
+    ///# use libmpv_client::*;
+    ///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+    ///#     let handle = Handle::from_ptr(ptr);
+    /// let tracks: Node = handle.get_property("track-list")?;
+    /// let id: i64 = tracks.pointer_as("/0/id")?;
+    ///#     Ok(())
+    ///# }
+    /// ```
+    pub fn pointer_as<T: FromNode>(&self, path: &str) -> Result<T> {
+        let node = self.pointer(path)?.clone();
+        match path.strip_prefix('/') {
+            Some(segments) => T::from_node(node).map_err(|e| error_at(e, segments)),
+            None => T::from_node(node),
+        }
+    }
+}
+
+impl Index<&str> for Node {
+    type Output = Node;
+
+    /// The value of `key` in a [`Node::Map`], or [`Node::None`] if `self` is not a map or does not contain `key`.
+    fn index(&self, key: &str) -> &Self::Output {
+        match self {
+            Node::Map(map) => map.get(key).unwrap_or(&NONE),
+            _ => &NONE,
+        }
+    }
+}
+
+impl Index<usize> for Node {
+    type Output = Node;
+
+    /// The element at `index` in a [`Node::Array`], or [`Node::None`] if `self` is not an array or `index` is out of bounds.
+    fn index(&self, index: usize) -> &Self::Output {
+        match self {
+            Node::Array(array) => array.get(index).unwrap_or(&NONE),
+            _ => &NONE,
+        }
+    }
+}
@@ -73,7 +73,7 @@ impl MpvRecvInternal for NodeArray {
 impl MpvSend for NodeArray {}
 impl MpvSendInternal for NodeArray {
     fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
-        let repr = self.to_mpv_repr();
+        let repr = self.to_mpv_repr()?;
 
         fun(repr.ptr() as *mut c_void)
     }
@@ -82,7 +82,7 @@ impl MpvSendInternal for NodeArray {
 impl ToMpvRepr for NodeArray {
     type ReprWrap<'a> = MpvNodeArray<'a>;
 
-    fn to_mpv_repr(&self) -> Self::ReprWrap<'_> {
+    fn to_mpv_repr(&self) -> Result<Self::ReprWrap<'_>> {
         let mut repr = MpvNodeArray {
             _original: PhantomData,
             _owned_reprs: Vec::with_capacity(self.len()),
@@ -95,13 +95,13 @@ impl ToMpvRepr for NodeArray {
         };
 
         for node in self {
-            let node_repr = node.to_mpv_repr();
+            let node_repr = node.to_mpv_repr()?;
             repr._flat_reprs.push(*node_repr.node);
             repr._owned_reprs.push(node_repr);
         }
 
         repr.node_list.values = repr._flat_reprs.as_ptr() as *mut _;
 
-        repr
+        Ok(repr)
     }
 }
\ No newline at end of file
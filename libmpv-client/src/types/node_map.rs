@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::ffi::{CStr, CString,c_char, c_int, c_void};
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
@@ -8,8 +7,26 @@ use crate::*;
 use crate::node::MpvNode;
 use crate::types::traits::{MpvFormat, MpvRecv, MpvRecvInternal, MpvRepr, MpvSend, MpvSendInternal, ToMpvRepr};
 
-/// A [`HashMap<String, Node>`], used only within a [`Node`], and only in specific situations.
-pub type NodeMap = HashMap<String, Node>;
+/// A [`HashMap<String, Node>`](std::collections::HashMap), used only within a [`Node`], and only in specific situations.
+///
+/// With the `preserve-order` feature, this is an [`IndexMap<String, Node>`](https://docs.rs/indexmap/latest/indexmap/map/struct.IndexMap.html) instead,
+/// which keeps entries in the order mpv sent them (or in which they were inserted), and sends them to mpv in that order.
+#[cfg(not(feature = "preserve-order"))]
+pub type NodeMap = std::collections::HashMap<String, Node>;
+/// An [`IndexMap<String, Node>`](indexmap::IndexMap), used only within a [`Node`], and only in specific situations.
+///
+/// Because the `preserve-order` feature is enabled, entries are kept in the order mpv sent them (or in which they were inserted),
+/// and are sent to mpv in that order.
+#[cfg(feature = "preserve-order")]
+pub type NodeMap = indexmap::IndexMap<String, Node>;
+
+/// Remove `key` from `map`. The order of the remaining entries is not preserved.
+pub(crate) fn take(map: &mut NodeMap, key: &str) -> Option<Node> {
+    #[cfg(not(feature = "preserve-order"))]
+    return map.remove(key);
+    #[cfg(feature = "preserve-order")]
+    return map.swap_remove(key);
+}
 
 #[derive(Debug)]
 pub(crate) struct MpvNodeMap<'a> {
@@ -48,6 +65,15 @@ impl From<&NodeMap> for Node {
     }
 }
 
+/// Read the key of a map entry, which must be valid UTF-8, since a [`NodeMap`] can only hold [`String`] keys.
+///
+/// # Safety
+/// `key` must be null or point to a C string which lives for `'k`.
+pub(crate) unsafe fn map_key<'k>(key: *const c_char) -> Result<&'k str> {
+    check_null!(key);
+    Ok(unsafe { CStr::from_ptr(key) }.to_str()?)
+}
+
 impl MpvRecv for NodeMap {}
 impl MpvRecvInternal for NodeMap {
     unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
@@ -68,7 +94,7 @@ impl MpvRecvInternal for NodeMap {
 
         let node_keys = unsafe { std::slice::from_raw_parts(node_list.keys, node_list.num as usize) };
         for node_key in node_keys {
-            keys.push(unsafe { CStr::from_ptr(*node_key) }.to_str()?.to_string());
+            keys.push(unsafe { map_key(*node_key) }?.to_string());
         }
 
         let map = keys.into_iter().zip(values).collect();
@@ -88,7 +114,7 @@ impl MpvRecvInternal for NodeMap {
 impl MpvSend for NodeMap {}
 impl MpvSendInternal for NodeMap {
     fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
-        let repr = self.to_mpv_repr();
+        let repr = self.to_mpv_repr()?;
 
         fun(repr.ptr() as *mut c_void)
     }
@@ -97,7 +123,7 @@ impl MpvSendInternal for NodeMap {
 impl ToMpvRepr for NodeMap {
     type ReprWrap<'a> = MpvNodeMap<'a>;
 
-    fn to_mpv_repr(&self) -> Self::ReprWrap<'_> {
+    fn to_mpv_repr(&self) -> Result<Self::ReprWrap<'_>> {
         let mut repr = MpvNodeMap {
             _original: PhantomData,
             _owned_reprs: Vec::with_capacity(self.len()),
@@ -112,12 +138,11 @@ impl ToMpvRepr for NodeMap {
         };
 
         for (key, value) in self {
-            // TODO: Remove this unwrap() by converting to_mpv_repr to return Result<>. See traits.rs.
-            let cstring = CString::new(key.as_bytes()).unwrap_or_default();
+            let cstring = CString::new(key.as_bytes())?;
             repr._flat_keys.push(cstring.as_ptr());
             repr._owned_keys.push(cstring);
 
-            let val_repr = value.to_mpv_repr();
+            let val_repr = value.to_mpv_repr()?;
             repr._flat_reprs.push(*val_repr.node);
             repr._owned_reprs.push(val_repr);
         }
@@ -125,6 +150,6 @@ impl ToMpvRepr for NodeMap {
         repr.node_list.values = repr._flat_reprs.as_ptr() as *mut _;
         repr.node_list.keys = repr._flat_keys.as_ptr() as *mut _;
 
-        repr
+        Ok(repr)
     }
 }
\ No newline at end of file
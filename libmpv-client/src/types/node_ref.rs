@@ -0,0 +1,208 @@
+#![allow(non_upper_case_globals)]
+
+use std::ffi::{CStr, c_char};
+use std::fmt::{Debug, Formatter};
+use libmpv_client_sys::{mpv_format_MPV_FORMAT_BYTE_ARRAY, mpv_format_MPV_FORMAT_DOUBLE, mpv_format_MPV_FORMAT_FLAG, mpv_format_MPV_FORMAT_INT64, mpv_format_MPV_FORMAT_NODE_ARRAY, mpv_format_MPV_FORMAT_NODE_MAP, mpv_format_MPV_FORMAT_NONE, mpv_format_MPV_FORMAT_STRING, mpv_format, mpv_node, mpv_node_list};
+use crate::*;
+use crate::types::node_map::map_key;
+
+/// An [`mpv_node`] owned by mpv, as returned by [`Handle::get_property_ref()`].
+///
+/// Unlike receiving a [`Node`], nothing is copied up front: the data is read in place through [`NodeGuard::get()`],
+/// and freed with `mpv_free_node_contents()` when the guard is dropped.
+/// This makes it much cheaper to pick a few values out of a large property, such as `playlist` or `demuxer-cache-state`.
+pub struct NodeGuard {
+    node: mpv_node,
+}
+
+impl NodeGuard {
+    /// Take ownership of `node`.
+    ///
+    /// # Safety
+    /// `node` must have been filled in by mpv, and must not be freed elsewhere.
+    pub(crate) unsafe fn from_raw(node: mpv_node) -> Self {
+        Self { node }
+    }
+
+    /// A borrowed view of the root of the node.
+    pub fn get(&self) -> NodeRef<'_> {
+        NodeRef { node: &self.node }
+    }
+}
+
+impl Drop for NodeGuard {
+    fn drop(&mut self) {
+        unsafe { mpv_free_node_contents(&mut self.node) }
+    }
+}
+
+impl Debug for NodeGuard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("NodeGuard").field(&self.get()).finish()
+    }
+}
+
+/// A borrowed, zero-copy view of a value inside a [`NodeGuard`].
+///
+/// The accessors mirror those of [`Node`], but only read the requested part of the data.
+/// Use [`NodeRef::to_owned()`] to copy (part of) it into a [`Node`].
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+///#
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// let playlist = handle.get_property_ref("playlist")?;
+///
+/// // Find the current entry without copying the rest of the playlist.
+/// let current = playlist.get().iter().find(|entry| entry.get("current").and_then(|c| c.as_bool()) == Some(true));
+/// if let Some(filename) = current.and_then(|entry| entry.get("filename")).and_then(|f| f.as_str()) {
+///     println!("playing {filename}");
+/// }
+///#     Ok(())
+///# }
+/// ```
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a> {
+    node: &'a mpv_node,
+}
+
+impl<'a> NodeRef<'a> {
+    fn is(&self, format: mpv_format) -> bool {
+        self.node.format == format
+    }
+
+    fn list(&self, format: mpv_format) -> Option<&'a mpv_node_list> {
+        if !self.is(format) {
+            return None;
+        }
+        unsafe { self.node.u.list.as_ref() }
+    }
+
+    /// Whether this is a [`Node::None`].
+    pub fn is_none(&self) -> bool {
+        self.is(mpv_format_MPV_FORMAT_NONE)
+    }
+
+    /// The contents of a string, if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&'a str> {
+        self.as_raw_str().and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    /// The raw bytes of a string, which may not be valid UTF-8 (see [`Node::RawString`]).
+    pub fn as_raw_str(&self) -> Option<&'a [u8]> {
+        if !self.is(mpv_format_MPV_FORMAT_STRING) {
+            return None;
+        }
+        let ptr = unsafe { self.node.u.string };
+        (!ptr.is_null()).then(|| unsafe { CStr::from_ptr(ptr) }.to_bytes())
+    }
+
+    /// The contents of a flag.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.node.format {
+            mpv_format_MPV_FORMAT_FLAG => Some(unsafe { self.node.u.flag } != 0),
+            _ => None,
+        }
+    }
+
+    /// The contents of an integer.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.node.format {
+            mpv_format_MPV_FORMAT_INT64 => Some(unsafe { self.node.u.int64 }),
+            _ => None,
+        }
+    }
+
+    /// The contents of a double, or of an integer converted to [`f64`].
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.node.format {
+            mpv_format_MPV_FORMAT_DOUBLE => Some(unsafe { self.node.u.double_ }),
+            mpv_format_MPV_FORMAT_INT64 => Some(unsafe { self.node.u.int64 } as f64),
+            _ => None,
+        }
+    }
+
+    /// The contents of a byte array.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        if !self.is(mpv_format_MPV_FORMAT_BYTE_ARRAY) {
+            return None;
+        }
+        let ba = unsafe { self.node.u.ba.as_ref() }?;
+        if ba.data.is_null() || ba.size == 0 {
+            return Some(&[]);
+        }
+        Some(unsafe { std::slice::from_raw_parts(ba.data as *const u8, ba.size) })
+    }
+
+    /// The number of entries of an array or map, or `0` for any other value.
+    pub fn len(&self) -> usize {
+        self.list(mpv_format_MPV_FORMAT_NODE_ARRAY)
+            .or_else(|| self.list(mpv_format_MPV_FORMAT_NODE_MAP))
+            .map_or(0, |list| list.num.max(0) as usize)
+    }
+
+    /// Whether this is an empty array or map, or any other value.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn values(list: &'a mpv_node_list) -> &'a [mpv_node] {
+        if list.values.is_null() || list.num <= 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(list.values, list.num as usize) }
+    }
+
+    fn keys(list: &'a mpv_node_list) -> &'a [*mut c_char] {
+        if list.keys.is_null() || list.num <= 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(list.keys, list.num as usize) }
+    }
+
+    /// The element at `index` of an array.
+    pub fn at(&self, index: usize) -> Option<NodeRef<'a>> {
+        let list = self.list(mpv_format_MPV_FORMAT_NODE_ARRAY)?;
+        Self::values(list).get(index).map(|node| NodeRef { node })
+    }
+
+    /// The value of `key` in a map.
+    pub fn get(&self, key: &str) -> Option<NodeRef<'a>> {
+        let list = self.list(mpv_format_MPV_FORMAT_NODE_MAP)?;
+        Self::keys(list).iter().zip(Self::values(list))
+            .find(|(k, _)| !k.is_null() && unsafe { CStr::from_ptr(**k) }.to_bytes() == key.as_bytes())
+            .map(|(_, node)| NodeRef { node })
+    }
+
+    /// Iterate over the elements of an array. Any other value has no elements.
+    pub fn iter(&self) -> impl Iterator<Item = NodeRef<'a>> + use<'a> {
+        self.list(mpv_format_MPV_FORMAT_NODE_ARRAY).map_or(&[][..], Self::values).iter().map(|node| NodeRef { node })
+    }
+
+    /// Iterate over the entries of a map. Any other value has no entries.
+    ///
+    /// As with [`NodeRef::to_owned()`], a key which is null or not valid UTF-8 is an error.
+    pub fn entries(&self) -> impl Iterator<Item = Result<(&'a str, NodeRef<'a>)>> + use<'a> {
+        let list = self.list(mpv_format_MPV_FORMAT_NODE_MAP);
+        let keys = list.map_or(&[][..], Self::keys);
+        let values = list.map_or(&[][..], Self::values);
+
+        keys.iter().zip(values).map(|(k, node)| Ok((unsafe { map_key(*k) }?, NodeRef { node })))
+    }
+
+    /// Copy this value, and everything nested in it, into an owned [`Node`].
+    pub fn to_owned(&self) -> Result<Node> {
+        unsafe { Node::from_node_ptr(self.node) }
+    }
+}
+
+impl Debug for NodeRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.to_owned() {
+            Ok(node) => node.fmt(f),
+            Err(e) => write!(f, "<{e:?}>"),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Support for strings which may not be valid UTF-8, such as filenames and file tags, through [`OsString`] and [`PathBuf`].
+//!
+//! On Unix, these carry mpv's raw bytes both ways. On other platforms, mpv always uses UTF-8,
+//! so invalid UTF-8 is rejected with [`RustError::InvalidUtf8`](crate::error::RustError::InvalidUtf8).
+
+use std::ffi::{CStr, CString, OsStr, OsString, c_char, c_void};
+use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
+use crate::*;
+use crate::types::convert::node_error;
+use crate::types::traits::{MpvFormat, MpvRecv, MpvRecvInternal, MpvSend, MpvSendInternal};
+
+/// Convert the raw bytes of an mpv string into an [`OsString`].
+pub(crate) fn bytes_to_os_string(bytes: Vec<u8>) -> Result<OsString> {
+    #[cfg(unix)]
+    return Ok(std::os::unix::ffi::OsStringExt::from_vec(bytes));
+    #[cfg(not(unix))]
+    return Ok(OsString::from(String::from_utf8(bytes).map_err(|e| e.utf8_error())?));
+}
+
+/// Convert an [`OsStr`] into the raw bytes of an mpv string.
+pub(crate) fn os_str_to_cstring(s: &OsStr) -> Result<CString> {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(s);
+    #[cfg(not(unix))]
+    let bytes = std::str::from_utf8(s.as_encoded_bytes())?.as_bytes();
+
+    Ok(CString::new(bytes)?)
+}
+
+/// Convert an [`OsStr`] into a [`Node::String`], or a [`Node::RawString`] if it is not valid UTF-8.
+fn os_str_to_node(s: &OsStr) -> Node {
+    match s.to_str() {
+        Some(s) => Node::String(s.to_string()),
+        #[cfg(unix)]
+        None => Node::RawString(std::os::unix::ffi::OsStrExt::as_bytes(s).to_vec()),
+        #[cfg(not(unix))]
+        None => Node::String(s.to_string_lossy().into_owned()),
+    }
+}
+
+impl MpvFormat for OsString {
+    const MPV_FORMAT: Format = Format::STRING;
+}
+
+impl MpvRecv for OsString {}
+impl MpvRecvInternal for OsString {
+    unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
+        check_null!(ptr);
+        let cstr = unsafe { *(ptr as *const *const c_char) };
+
+        check_null!(cstr);
+        bytes_to_os_string(unsafe { CStr::from_ptr(cstr) }.to_bytes().to_vec())
+    }
+
+    unsafe fn from_mpv<F: Fn(*mut c_void) -> Result<i32>>(fun: F) -> Result<Self> {
+        let mut cstr: *mut c_char = null_mut();
+
+        fun(&raw mut cstr as *mut c_void).and_then(|_| {
+            let ret = unsafe { Self::from_ptr(&raw mut cstr as *const c_void) };
+            unsafe { mpv_free(cstr as *mut c_void) }
+            ret
+        })
+    }
+}
+
+impl MpvSend for OsString {}
+impl MpvSendInternal for OsString {
+    fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+        self.as_os_str().to_mpv(fun)
+    }
+}
+
+impl MpvFormat for &OsStr {
+    const MPV_FORMAT: Format = Format::STRING;
+}
+
+impl MpvSend for &OsStr {}
+impl MpvSendInternal for &OsStr {
+    fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+        let cstring = os_str_to_cstring(self)?;
+        let cstr = cstring.as_ptr();
+
+        fun(&raw const cstr as *mut c_void)
+    }
+}
+
+impl MpvFormat for PathBuf {
+    const MPV_FORMAT: Format = Format::STRING;
+}
+
+impl MpvRecv for PathBuf {}
+impl MpvRecvInternal for PathBuf {
+    unsafe fn from_ptr(ptr: *const c_void) -> Result<Self> {
+        unsafe { OsString::from_ptr(ptr) }.map(PathBuf::from)
+    }
+
+    unsafe fn from_mpv<F: Fn(*mut c_void) -> Result<i32>>(fun: F) -> Result<Self> {
+        unsafe { OsString::from_mpv(fun) }.map(PathBuf::from)
+    }
+}
+
+impl MpvSend for PathBuf {}
+impl MpvSendInternal for PathBuf {
+    fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+        self.as_os_str().to_mpv(fun)
+    }
+}
+
+impl MpvFormat for &Path {
+    const MPV_FORMAT: Format = Format::STRING;
+}
+
+impl MpvSend for &Path {}
+impl MpvSendInternal for &Path {
+    fn to_mpv<F: Fn(*mut c_void) -> Result<i32>>(&self, fun: F) -> Result<i32> {
+        self.as_os_str().to_mpv(fun)
+    }
+}
+
+impl From<&OsStr> for Node {
+    /// Create a [`Node::String`], or a [`Node::RawString`] if `value` is not valid UTF-8.
+    fn from(value: &OsStr) -> Self {
+        os_str_to_node(value)
+    }
+}
+
+impl From<OsString> for Node {
+    /// Create a [`Node::String`], or a [`Node::RawString`] if `value` is not valid UTF-8.
+    fn from(value: OsString) -> Self {
+        os_str_to_node(&value)
+    }
+}
+
+impl From<&Path> for Node {
+    /// Create a [`Node::String`], or a [`Node::RawString`] if `value` is not valid UTF-8.
+    fn from(value: &Path) -> Self {
+        os_str_to_node(value.as_os_str())
+    }
+}
+
+impl From<PathBuf> for Node {
+    /// Create a [`Node::String`], or a [`Node::RawString`] if `value` is not valid UTF-8.
+    fn from(value: PathBuf) -> Self {
+        os_str_to_node(value.as_os_str())
+    }
+}
+
+impl FromNode for OsString {
+    fn from_node(node: Node) -> Result<Self> {
+        match node {
+            Node::String(s) => Ok(OsString::from(s)),
+            Node::RawString(bytes) => bytes_to_os_string(bytes),
+            other => Err(node_error("a string", other.type_name())),
+        }
+    }
+}
+
+impl ToNode for OsString {
+    fn to_node(&self) -> Node {
+        os_str_to_node(self)
+    }
+}
+
+impl FromNode for PathBuf {
+    fn from_node(node: Node) -> Result<Self> {
+        OsString::from_node(node).map(PathBuf::from)
+    }
+}
+
+impl ToNode for PathBuf {
+    fn to_node(&self) -> Node {
+        os_str_to_node(self.as_os_str())
+    }
+}
@@ -0,0 +1,625 @@
+//! [`serde`] support for [`Node`], enabled with the `serde` feature.
+
+use std::fmt::{Display, Formatter};
+use ::serde::de::value::{MapDeserializer, SeqDeserializer};
+use ::serde::de::{DeserializeOwned, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+use ::serde::ser::{Impossible, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant};
+use ::serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use crate::*;
+use crate::error::RustError;
+
+/// Serialize any [`Serialize`] value into a [`Node`]. Requires the `serde` feature.
+///
+/// Rust values map onto [`Node`] as follows:
+/// - `bool` ⇔ [`Node::Flag`], integers ⇔ [`Node::Int64`], floats ⇔ [`Node::Double`], `char` and strings ⇔ [`Node::String`].
+/// - Sequences, tuples and tuple structs ⇔ [`Node::Array`]. A non-empty sequence of `u8` (such as `Vec<u8>`) becomes a [`Node::ByteArray`].
+/// - Maps and structs ⇔ [`Node::Map`]. Map keys must be strings, chars or integers.
+/// - `None`, `()` and unit structs ⇔ [`Node::None`].
+/// - Unit enum variants ⇔ [`Node::String`] of the variant name, other variants ⇔ a single-entry [`Node::Map`] of `{ variant: value }`.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+/// #[derive(serde::Serialize)]
+/// struct ShowText {
+///     name: &'static str,
+///     text: String,
+///     duration: i64,
+/// }
+///
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// handle.command_node(to_node(&ShowText { name: "show-text", text: "hello".to_string(), duration: 500 })?)?;
+///#     Ok(())
+///# }
+/// ```
+pub fn to_node<T: Serialize + ?Sized>(value: &T) -> Result<Node> {
+    Ok(value.serialize(NodeSerializer)?)
+}
+
+/// Deserialize any [`DeserializeOwned`] value from a [`Node`]. Requires the `serde` feature.
+///
+/// Values are mapped as described in [`to_node()`]. In addition, a [`Node::ByteArray`] can be deserialized as any sequence of `u8`.
+///
+/// # Example
+/// ```
+///# use libmpv_client::*;
+/// #[derive(serde::Deserialize)]
+/// #[serde(rename_all = "kebab-case")]
+/// struct Track {
+///     id: i64,
+///     r#type: String,
+///     title: Option<String>,
+///     external_filename: Option<String>,
+/// }
+///
+///# fn example_func(ptr: *mut mpv_handle) -> Result<()> {
+///#     let handle = Handle::from_ptr(ptr);
+/// let tracks: Vec<Track> = from_node(handle.get_property::<Node>("track-list")?)?;
+///#     Ok(())
+///# }
+/// ```
+pub fn from_node<T: DeserializeOwned>(node: Node) -> Result<T> {
+    Ok(T::deserialize(NodeDeserializer(node))?)
+}
+
+/// The error type used internally by the [`Serializer`] and [`Deserializer`], converted into [`RustError::Serde`] at the API boundary.
+#[derive(Debug)]
+struct SerdeError(String);
+
+impl Display for SerdeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+impl ser::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError(msg.to_string())
+    }
+}
+
+impl de::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError(msg.to_string())
+    }
+}
+
+impl From<SerdeError> for Error {
+    fn from(value: SerdeError) -> Self {
+        Error::Rust(RustError::Serde(value.0))
+    }
+}
+
+type SerdeResult<T> = std::result::Result<T, SerdeError>;
+
+impl Serialize for Node {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Node::None => serializer.serialize_none(),
+            Node::String(s) => serializer.serialize_str(s),
+            Node::Flag(b) => serializer.serialize_bool(*b),
+            Node::Int64(i) => serializer.serialize_i64(*i),
+            Node::Double(d) => serializer.serialize_f64(*d),
+            Node::Array(array) => array.serialize(serializer),
+            Node::Map(map) => serializer.collect_map(map),
+            Node::ByteArray(bytes) | Node::RawString(bytes) => serializer.serialize_bytes(bytes),
+            Node::Unknown(format) => Err(ser::Error::custom(format!("mpv node of unknown format {format}"))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("any value representable as an mpv node")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<Node, E> {
+        Ok(Node::Flag(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Node, E> {
+        Ok(Node::Int64(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Node, E> {
+        i64::try_from(v).map(Node::Int64).map_err(|_| E::custom(format!("integer {v} is out of range for an mpv node")))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<Node, E> {
+        Ok(Node::Double(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Node, E> {
+        Ok(Node::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<Node, E> {
+        Ok(Node::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Node, E> {
+        Ok(Node::ByteArray(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> std::result::Result<Node, E> {
+        Ok(Node::ByteArray(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<Node, E> {
+        Ok(Node::None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<Node, D::Error> {
+        Node::deserialize(deserializer)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Node, E> {
+        Ok(Node::None)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Node, A::Error> {
+        let mut array = NodeArray::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            array.push(value);
+        }
+        Ok(Node::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> std::result::Result<Node, A::Error> {
+        let mut map = NodeMap::new();
+        while let Some((key, value)) = access.next_entry()? {
+            map.insert(key, value);
+        }
+        Ok(Node::Map(map))
+    }
+}
+
+/// A [`Serializer`] producing [`Node`]s.
+struct NodeSerializer;
+
+impl Serializer for NodeSerializer {
+    type Ok = Node;
+    type Error = SerdeError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = VariantSerializer<MapSerializer>;
+
+    fn serialize_bool(self, v: bool) -> SerdeResult<Node> {
+        Ok(Node::Flag(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> SerdeResult<Node> {
+        Ok(Node::Int64(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> SerdeResult<Node> {
+        Ok(Node::Int64(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> SerdeResult<Node> {
+        Ok(Node::Int64(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> SerdeResult<Node> {
+        Ok(Node::Int64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> SerdeResult<Node> {
+        Ok(Node::Int64(v as i64))
+    }
+
+    fn serialize_u16(self, v: u16) -> SerdeResult<Node> {
+        Ok(Node::Int64(v as i64))
+    }
+
+    fn serialize_u32(self, v: u32) -> SerdeResult<Node> {
+        Ok(Node::Int64(v as i64))
+    }
+
+    fn serialize_u64(self, v: u64) -> SerdeResult<Node> {
+        i64::try_from(v).map(Node::Int64).map_err(|_| SerdeError(format!("integer {v} is out of range for an mpv node")))
+    }
+
+    fn serialize_f32(self, v: f32) -> SerdeResult<Node> {
+        Ok(Node::Double(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> SerdeResult<Node> {
+        Ok(Node::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> SerdeResult<Node> {
+        Ok(Node::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> SerdeResult<Node> {
+        Ok(Node::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> SerdeResult<Node> {
+        Ok(Node::ByteArray(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> SerdeResult<Node> {
+        Ok(Node::None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> SerdeResult<Node> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> SerdeResult<Node> {
+        Ok(Node::None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> SerdeResult<Node> {
+        Ok(Node::None)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> SerdeResult<Node> {
+        Ok(Node::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> SerdeResult<Node> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> SerdeResult<Node> {
+        Ok(Node::Map(NodeMap::from([(variant.to_string(), value.serialize(self)?)])))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> SerdeResult<SeqSerializer> {
+        Ok(SeqSerializer { bytes: Vec::new(), nodes: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> SerdeResult<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> SerdeResult<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> SerdeResult<VariantSerializer<SeqSerializer>> {
+        Ok(VariantSerializer { variant, inner: self.serialize_seq(Some(len))? })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> SerdeResult<MapSerializer> {
+        Ok(MapSerializer { map: NodeMap::new(), key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> SerdeResult<MapSerializer> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> SerdeResult<VariantSerializer<MapSerializer>> {
+        Ok(VariantSerializer { variant, inner: self.serialize_map(Some(len))? })
+    }
+}
+
+/// Serializes sequences into a [`Node::Array`], or a [`Node::ByteArray`] if every element is a `u8`.
+struct SeqSerializer {
+    /// Elements serialized so far, as long as every one of them was a `u8`.
+    bytes: Vec<u8>,
+    /// All elements, once any of them was not a `u8`.
+    nodes: NodeArray,
+}
+
+impl SerializeSeq for SeqSerializer {
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        if self.nodes.is_empty() && let Ok(byte) = value.serialize(ByteProbe) {
+            self.bytes.push(byte);
+            return Ok(());
+        }
+
+        if !self.bytes.is_empty() {
+            self.nodes.extend(self.bytes.drain(..).map(|byte| Node::Int64(byte as i64)));
+        }
+
+        self.nodes.push(value.serialize(NodeSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> SerdeResult<Node> {
+        if self.bytes.is_empty() {
+            Ok(Node::Array(self.nodes))
+        } else {
+            Ok(Node::ByteArray(self.bytes))
+        }
+    }
+}
+
+impl SerializeTuple for SeqSerializer {
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> SerdeResult<Node> {
+        // Tuples are heterogeneous, so a tuple of bytes stays an array.
+        let mut nodes = self.nodes;
+        nodes.extend(self.bytes.into_iter().map(|byte| Node::Int64(byte as i64)));
+        Ok(Node::Array(nodes))
+    }
+}
+
+impl SerializeTupleStruct for SeqSerializer {
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> SerdeResult<Node> {
+        SerializeTuple::end(self)
+    }
+}
+
+/// Serializes maps and structs into a [`Node::Map`].
+struct MapSerializer {
+    map: NodeMap,
+    key: Option<String>,
+}
+
+impl SerializeMap for MapSerializer {
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> SerdeResult<()> {
+        self.key = Some(match key.serialize(NodeSerializer)? {
+            Node::String(s) => s,
+            Node::Int64(i) => i.to_string(),
+            _ => return Err(SerdeError("mpv node map keys must be strings".to_string())),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        let key = self.key.take().ok_or_else(|| SerdeError("map value serialized without a key".to_string()))?;
+        self.map.insert(key, value.serialize(NodeSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> SerdeResult<Node> {
+        Ok(Node::Map(self.map))
+    }
+}
+
+impl SerializeStruct for MapSerializer {
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> SerdeResult<()> {
+        self.map.insert(key.to_string(), value.serialize(NodeSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> SerdeResult<Node> {
+        Ok(Node::Map(self.map))
+    }
+}
+
+/// Wraps the contents of a tuple or struct variant into a single-entry `{ variant: value }` [`Node::Map`].
+struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> SerdeResult<Node> {
+        Ok(Node::Map(NodeMap::from([(self.variant.to_string(), SerializeTuple::end(self.inner)?)])))
+    }
+}
+
+impl SerializeStructVariant for VariantSerializer<MapSerializer> {
+    type Ok = Node;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> SerdeResult<()> {
+        SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> SerdeResult<Node> {
+        Ok(Node::Map(NodeMap::from([(self.variant.to_string(), SerializeStruct::end(self.inner)?)])))
+    }
+}
+
+/// A [`Serializer`] which only accepts a `u8`, used to detect byte sequences.
+///
+/// Any other value is rejected on the first call, so probing is cheap.
+struct ByteProbe;
+
+macro_rules! probe_reject {
+    ($($name:ident($($arg:ty),*) -> $ret:ty;)*) => {
+        $(fn $name(self, $(_: $arg),*) -> SerdeResult<$ret> {
+            Err(SerdeError(String::new()))
+        })*
+    };
+}
+
+impl Serializer for ByteProbe {
+    type Ok = u8;
+    type Error = SerdeError;
+    type SerializeSeq = Impossible<u8, SerdeError>;
+    type SerializeTuple = Impossible<u8, SerdeError>;
+    type SerializeTupleStruct = Impossible<u8, SerdeError>;
+    type SerializeTupleVariant = Impossible<u8, SerdeError>;
+    type SerializeMap = Impossible<u8, SerdeError>;
+    type SerializeStruct = Impossible<u8, SerdeError>;
+    type SerializeStructVariant = Impossible<u8, SerdeError>;
+
+    fn serialize_u8(self, v: u8) -> SerdeResult<u8> {
+        Ok(v)
+    }
+
+    probe_reject! {
+        serialize_bool(bool) -> u8;
+        serialize_i8(i8) -> u8;
+        serialize_i16(i16) -> u8;
+        serialize_i32(i32) -> u8;
+        serialize_i64(i64) -> u8;
+        serialize_u16(u16) -> u8;
+        serialize_u32(u32) -> u8;
+        serialize_u64(u64) -> u8;
+        serialize_f32(f32) -> u8;
+        serialize_f64(f64) -> u8;
+        serialize_char(char) -> u8;
+        serialize_str(&str) -> u8;
+        serialize_bytes(&[u8]) -> u8;
+        serialize_none() -> u8;
+        serialize_unit() -> u8;
+        serialize_unit_struct(&'static str) -> u8;
+        serialize_unit_variant(&'static str, u32, &'static str) -> u8;
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> SerdeResult<u8> {
+        Err(SerdeError(String::new()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, _value: &T) -> SerdeResult<u8> {
+        Err(SerdeError(String::new()))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, _variant: &'static str, _value: &T) -> SerdeResult<u8> {
+        Err(SerdeError(String::new()))
+    }
+}
+
+/// A [`Deserializer`] consuming a [`Node`].
+struct NodeDeserializer(Node);
+
+impl<'de> IntoDeserializer<'de, SerdeError> for NodeDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for NodeDeserializer {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.0 {
+            Node::None => visitor.visit_unit(),
+            Node::String(s) => visitor.visit_string(s),
+            Node::Flag(b) => visitor.visit_bool(b),
+            Node::Int64(i) => visitor.visit_i64(i),
+            Node::Double(d) => visitor.visit_f64(d),
+            Node::Array(array) => visitor.visit_seq(SeqDeserializer::new(array.into_iter().map(NodeDeserializer))),
+            Node::Map(map) => visitor.visit_map(MapDeserializer::new(map.into_iter().map(|(k, v)| (k, NodeDeserializer(v))))),
+            Node::ByteArray(bytes) | Node::RawString(bytes) => visitor.visit_byte_buf(bytes),
+            Node::Unknown(format) => Err(SerdeError(format!("mpv node of unknown format {format}"))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.0 {
+            Node::None => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        // Allow a `ByteArray` to be deserialized as a sequence, such as `Vec<u8>`.
+        match self.0 {
+            Node::ByteArray(bytes) => visitor.visit_seq(SeqDeserializer::new(bytes.into_iter())),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> SerdeResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> SerdeResult<V::Value> {
+        match self.0 {
+            Node::String(variant) => visitor.visit_enum(EnumDeserializer { variant, value: Node::None }),
+            Node::Map(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer { variant, value })
+            }
+            _ => Err(SerdeError("expected a string or a single-entry map for an enum".to_string())),
+        }
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Node,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = SerdeError;
+    type Variant = NodeDeserializer;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> SerdeResult<(V::Value, NodeDeserializer)> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, NodeDeserializer(self.value)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for NodeDeserializer {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> SerdeResult<()> {
+        match self.0 {
+            Node::None => Ok(()),
+            _ => Err(SerdeError("expected a unit variant".to_string())),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> SerdeResult<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> SerdeResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> SerdeResult<V::Value> {
+        self.deserialize_any(visitor)
+    }
+}
@@ -1,7 +1,6 @@
 #![cfg(test)]
 #![allow(non_upper_case_globals)]
 
-use std::collections::HashMap;
 use std::ffi::{c_char, c_int, c_void, CStr, CString};
 use std::ptr::null_mut;
 use libmpv_client_sys::{mpv_byte_array, mpv_format_MPV_FORMAT_DOUBLE, mpv_format_MPV_FORMAT_FLAG, mpv_format_MPV_FORMAT_INT64, mpv_format_MPV_FORMAT_NODE_ARRAY, mpv_format_MPV_FORMAT_NODE_MAP, mpv_format_MPV_FORMAT_NONE, mpv_format_MPV_FORMAT_STRING, mpv_node, mpv_node__bindgen_ty_1, mpv_node_list};
@@ -389,7 +388,7 @@ fn node_complex_from_mpv() {
         })
     }.unwrap();
 
-    let test_map = Node::Map(HashMap::from([
+    let test_map = Node::Map(NodeMap::from([
         ("none".to_string(), Node::None),
         ("string".to_string(), Node::String("hello, world!".to_string())),
         ("flag".to_string(), Node::Flag(true)),
@@ -400,7 +399,7 @@ fn node_complex_from_mpv() {
             Node::Flag(true),
             Node::Int64(2),
             Node::Double(3.3),
-            Node::Map(HashMap::from([
+            Node::Map(NodeMap::from([
                 ("4".to_string(), Node::String("five".to_string())),
                 ("six".to_string(), Node::Int64(7)),
             ]))
@@ -420,7 +419,7 @@ fn node_complex_from_mpv() {
 
 #[test]
 fn node_complex_to_mpv() {
-    let test_map = Node::Map(HashMap::from([
+    let test_map = Node::Map(NodeMap::from([
         ("none".to_string(), Node::None),
         ("string".to_string(), Node::String("hello, world!".to_string())),
         ("flag".to_string(), Node::Flag(true)),
@@ -431,7 +430,7 @@ fn node_complex_to_mpv() {
             Node::Flag(true),
             Node::Int64(2),
             Node::Double(3.3),
-            Node::Map(HashMap::from([
+            Node::Map(NodeMap::from([
                 ("4".to_string(), Node::String("five".to_string())),
                 ("six".to_string(), Node::Int64(7)),
             ]))
@@ -610,7 +609,7 @@ fn nodemap_from_mpv() {
         Ok(0)
     }) }.unwrap();
 
-    let map = HashMap::from([
+    let map = NodeMap::from([
         ("first".to_string(), Node::String("hello, world!".to_string())),
         ("second".to_string(), Node::Flag(true)),
         ("third".to_string(), Node::Int64(123456)),
@@ -625,7 +624,7 @@ fn nodemap_from_mpv() {
 
 #[test]
 fn nodemap_to_mpv() {
-    let map = HashMap::from([
+    let map = NodeMap::from([
         ("first".to_string(), Node::String("hello, world!".to_string())),
         ("second".to_string(), Node::Flag(true)),
         ("third".to_string(), Node::Int64(123456)),
@@ -817,7 +816,7 @@ fn node_huge_round_trip() {
         Node::String(String::from("hello, world!9")),
     ]);
 
-    let hello_map = Node::Map(HashMap::from([
+    let hello_map = Node::Map(NodeMap::from([
         ("hello1".to_string(), hello_worlds.clone()),
         ("hello2".to_string(), hello_worlds.clone()),
         ("hello3".to_string(), hello_worlds.clone()),
@@ -829,7 +828,7 @@ fn node_huge_round_trip() {
         ("hello9".to_string(), hello_worlds.clone()),
     ]));
 
-    let number_map = Node::Map(HashMap::from([
+    let number_map = Node::Map(NodeMap::from([
         ("1".to_string(), hello_map.clone()),
         ("2".to_string(), hello_map.clone()),
         ("3".to_string(), hello_map.clone()),
@@ -864,7 +863,7 @@ fn node_huge_round_trip() {
         number_map.clone(),
     ]);
 
-    let node = Node::Map(HashMap::from([
+    let node = Node::Map(NodeMap::from([
         ("number_array".to_string(), number_array.clone()),
         ("number_map".to_string(), number_map.clone()),
         ("hello_map".to_string(), hello_map.clone()),
@@ -875,7 +874,7 @@ fn node_huge_round_trip() {
         ("number_array2".to_string(), number_array.clone()),
     ]));
 
-    let mpv_repr = node.to_mpv_repr();
+    let mpv_repr = node.to_mpv_repr().unwrap();
 
     let test_node = unsafe { Node::from_node_ptr(&raw const *mpv_repr.node) }.unwrap();
 
@@ -883,4 +882,564 @@ fn node_huge_round_trip() {
 
     assert_eq!(tests::MPV_FREE_CALLS.get(), 0);
     assert_eq!(tests::MPV_FREE_NODE_CONTENTS_CALLS.get(), 0);
-}
\ No newline at end of file
+}
+#[test]
+fn node_accessors() {
+    let node = node_map! {
+        ("title", "x"),
+        ("pos", 3),
+        ("list", node_array!(1.5, true)),
+    };
+
+    assert_eq!(node["title"].as_str(), Some("x"));
+    assert_eq!(node["pos"].as_f64(), Some(3.0));
+    assert_eq!(node["pos"].as_i64(), Some(3));
+    assert_eq!(node["list"][1].as_bool(), Some(true));
+    assert!(node["missing"][5]["deeper"].is_none());
+    assert_eq!(node["list"].as_array().map(Vec::len), Some(2));
+    assert_eq!(node.as_str(), None);
+
+    assert_eq!(String::try_from(Node::from("a")).unwrap(), "a");
+    assert!(i64::try_from(Node::Double(1.0)).is_err());
+}
+
+#[test]
+fn node_pointer() {
+    use crate::error::{Error, RustError};
+    let path_of = |e: Error| match e {
+        Error::Rust(RustError::NodeConversion(e)) => (e.path, e.expected),
+        e => panic!("unexpected error: {e:?}"),
+    };
+
+    let node = node_map! {
+        ("track-list", node_array!(node_map! { ("lang", "eng"), ("a/b", 1) })),
+    };
+
+    assert_eq!(node.pointer("").unwrap(), &node);
+    assert_eq!(node.pointer("/track-list/0/lang").unwrap(), &Node::from("eng"));
+    assert_eq!(node.pointer("/track-list/0/a~1b").unwrap(), &Node::Int64(1));
+    assert_eq!(path_of(node.pointer("/track-list/1/lang").unwrap_err()), ("/track-list/1".to_string(), "a value"));
+    assert_eq!(path_of(node.pointer("/track-list/0/lang/x").unwrap_err()), ("/track-list/0/lang".to_string(), "a map or an array"));
+    assert_eq!(path_of(node.pointer_as::<i64>("/track-list/0/lang").unwrap_err()), ("/track-list/0/lang".to_string(), "an integer"));
+    assert_eq!(node.pointer_as::<Option<String>>("/track-list/0/lang").unwrap(), Some("eng".to_string()));
+}
+
+#[cfg(feature = "preserve-order")]
+#[test]
+fn nodemap_preserves_order() {
+    use crate::types::traits::MpvRepr;
+
+    let json = r#"{"zeta":1,"alpha":{"b":true,"a":false},"mid":"x"}"#;
+    let node = Node::from_json_str(json).unwrap();
+
+    let keys: Vec<&str> = node.as_map().unwrap().keys().map(String::as_str).collect();
+    assert_eq!(keys, ["zeta", "alpha", "mid"]);
+    assert_eq!(node.to_json_string().unwrap(), json);
+
+    let map = node_map! { ("c", 1), ("b", 2), ("a", 3) };
+    let round_trip = unsafe { NodeMap::from_ptr(map.as_map().unwrap().to_mpv_repr().unwrap().ptr() as *const c_void) }.unwrap();
+    assert_eq!(round_trip.keys().collect::<Vec<_>>(), ["c", "b", "a"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_string_from_mpv() {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+    use std::path::PathBuf;
+
+    let cstring = CString::new(b"caf\xE9.mkv".to_vec()).unwrap();
+    let cstr = cstring.as_ptr();
+
+    assert!(unsafe { String::from_ptr(&raw const cstr as *const c_void) }.is_err());
+
+    let os_string = unsafe { OsString::from_mpv(|x| {
+        *(x as *mut *const c_char) = cstring.as_ptr();
+        Ok(0)
+    }) }.unwrap();
+    assert_eq!(os_string.clone().into_vec(), b"caf\xE9.mkv");
+    assert_eq!(tests::MPV_FREE_CALLS.get(), 1);
+
+    let path = unsafe { PathBuf::from_ptr(&raw const cstr as *const c_void) }.unwrap();
+    assert_eq!(path.into_os_string(), os_string);
+
+    let cnode = mpv_node {
+        u: mpv_node__bindgen_ty_1 { string: cstring.as_ptr() as *mut c_char },
+        format: mpv_format_MPV_FORMAT_STRING,
+    };
+    let node = unsafe { Node::from_ptr(&raw const cnode as *const c_void) }.unwrap();
+    assert_eq!(node, Node::RawString(b"caf\xE9.mkv".to_vec()));
+    assert_eq!(Node::from(os_string.as_os_str()), node);
+
+    node.to_mpv(|x| {
+        let node = unsafe { *(x as *const mpv_node) };
+        assert_eq!(node.format, mpv_format_MPV_FORMAT_STRING);
+        assert_eq!(unsafe { CStr::from_ptr(node.u.string) }.to_bytes(), b"caf\xE9.mkv");
+        Ok(0)
+    }).unwrap();
+}
+
+#[test]
+fn checked_numbers_from_mpv() {
+    use std::time::Duration;
+    use crate::error::{Error, RustError};
+
+    let int = |value: i64| move |x: *mut c_void| {
+        unsafe { *(x as *mut i64) = value };
+        Ok(0)
+    };
+
+    assert_eq!(unsafe { i32::from_mpv(int(-5)) }.unwrap(), -5);
+    assert_eq!(unsafe { usize::from_mpv(int(7)) }.unwrap(), 7);
+    assert!(matches!(unsafe { u32::from_mpv(int(-1)) }, Err(Error::Rust(RustError::OutOfRange(e))) if e.value == "-1" && e.target == "u32"));
+    assert!(matches!(u64::MAX.to_mpv(|_| Ok(0)), Err(Error::Rust(RustError::OutOfRange(_)))));
+
+    let double = |value: f64| move |x: *mut c_void| {
+        unsafe { *(x as *mut f64) = value };
+        Ok(0)
+    };
+
+    assert_eq!(unsafe { f32::from_mpv(double(0.5)) }.unwrap(), 0.5);
+    assert!(unsafe { f32::from_mpv(double(1e300)) }.is_err());
+    assert_eq!(unsafe { Duration::from_mpv(double(1.5)) }.unwrap(), Duration::from_millis(1500));
+    assert!(unsafe { Duration::from_mpv(double(-1.0)) }.is_err());
+
+    Duration::from_secs(2).to_mpv(|x| {
+        assert_eq!(unsafe { *(x as *const f64) }, 2.0);
+        Ok(0)
+    }).unwrap();
+}
+
+#[test]
+fn option_from_mpv() {
+    use crate::error::Error;
+
+    assert_eq!(unsafe { Option::<i64>::from_mpv(|_| Err(Error::PropertyUnavailable)) }.unwrap(), None);
+    assert!(unsafe { Option::<i64>::from_mpv(|_| Err(Error::PropertyNotFound)) }.is_err());
+    assert_eq!(unsafe { Option::<i64>::from_mpv(|x| {
+        *(x as *mut i64) = 3;
+        Ok(0)
+    }) }.unwrap(), Some(3));
+}
+
+#[test]
+fn collections_through_node() {
+    let list = vec!["a".to_string(), "b".to_string()];
+
+    let received = list.to_mpv(|x| {
+        let node = unsafe { *(x as *const mpv_node) };
+        assert_eq!(node.format, mpv_format_MPV_FORMAT_NODE_ARRAY);
+        let received = unsafe { Vec::<String>::from_ptr(x) };
+        received.map(|r| r.len() as i32)
+    }).unwrap();
+    assert_eq!(received, 2);
+
+    let map = std::collections::HashMap::from([("volume".to_string(), 50u32)]);
+    map.to_mpv(|x| {
+        let received = unsafe { std::collections::HashMap::<String, u32>::from_ptr(x) }.unwrap();
+        assert_eq!(received["volume"], 50);
+        Ok(0)
+    }).unwrap();
+}
+
+/// The [`NodeError`](crate::error::NodeError) of a failed [`FromNode`](crate::FromNode) conversion.
+fn conversion_error(error: crate::Error) -> crate::error::NodeError {
+    use crate::error::{Error, RustError};
+
+    match error {
+        Error::Rust(RustError::NodeConversion(e)) => e,
+        e => panic!("unexpected error: {e:?}"),
+    }
+}
+
+#[test]
+fn from_node_fields() {
+    use crate::FromNode;
+
+    assert_eq!(i32::from_node(Node::Int64(-5)).unwrap(), -5);
+    assert!(u8::from_node(Node::Int64(256)).is_err());
+    assert_eq!(f64::from_node(Node::Int64(2)).unwrap(), 2.0);
+    assert_eq!(Option::<String>::from_node(Node::None).unwrap(), None);
+    assert_eq!(Vec::<u8>::from_node(Node::ByteArray(vec![1, 2])).unwrap(), vec![1, 2]);
+
+    let e = conversion_error(Vec::<i64>::from_node(node_array!(1, "two")).unwrap_err());
+    assert_eq!((e.path.as_str(), e.expected, e.found.as_str()), ("/1", "an integer", "a string"));
+}
+
+#[cfg(feature = "derive")]
+#[derive(crate::MpvFormat, crate::MpvRecv, crate::MpvSend, Debug, PartialEq)]
+enum DeriveHwdec {
+    No,
+    AutoSafe,
+    #[mpv(rename = "vaapi")]
+    VaApi,
+}
+
+#[cfg(feature = "derive")]
+#[derive(crate::MpvFormat, crate::MpvRecv, crate::MpvSend, Debug, PartialEq)]
+struct DeriveTrack {
+    id: i64,
+    #[mpv(rename = "type")]
+    kind: String,
+    title: Option<String>,
+    #[mpv(default)]
+    demux_w: i32,
+    hwdec: DeriveHwdec,
+    #[mpv(skip)]
+    ignored: bool,
+}
+
+#[cfg(feature = "derive")]
+#[derive(crate::MpvFormat, crate::MpvRecv, crate::MpvSend, Debug, PartialEq)]
+struct DerivePoint(f64, f64);
+
+#[cfg(feature = "derive")]
+#[derive(crate::MpvFormat, crate::MpvRecv, crate::MpvSend, Debug, PartialEq)]
+struct DeriveTrackId(i64);
+
+#[cfg(feature = "derive")]
+#[test]
+fn derived_conversions() {
+    use crate::{FromNode, ToNode};
+
+    let node = node_map! {
+        ("id", 1),
+        ("type", "video"),
+        ("hwdec", "auto-safe"),
+        ("unknown", true),
+    };
+
+    let track = DeriveTrack::from_node(node).unwrap();
+    assert_eq!(track, DeriveTrack { id: 1, kind: "video".to_string(), title: None, demux_w: 0, hwdec: DeriveHwdec::AutoSafe, ignored: false });
+
+    assert_eq!(track.to_node(), node_map! {
+        ("id", 1),
+        ("type", "video"),
+        ("demux-w", 0),
+        ("hwdec", "auto-safe"),
+    });
+
+    assert_eq!(DeriveHwdec::from_node(Node::Flag(false)).unwrap(), DeriveHwdec::No);
+    assert_eq!(DeriveHwdec::VaApi.to_node(), Node::from("vaapi"));
+    assert_eq!(DerivePoint::from_node(node_array!(1.0, 2)).unwrap(), DerivePoint(1.0, 2.0));
+    assert_eq!(DeriveTrackId(3).to_node(), Node::Int64(3));
+    assert_eq!(Vec::<DeriveTrackId>::from_node(node_array!(1, 2)).unwrap(), vec![DeriveTrackId(1), DeriveTrackId(2)]);
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derived_conversion_errors() {
+    use crate::FromNode;
+
+    let e = conversion_error(DeriveTrack::from_node(node_map! { ("id", 1), ("type", "video"), ("hwdec", "magic") }).unwrap_err());
+    assert_eq!(e.path, "/hwdec");
+    assert_eq!(e.found, "`magic`");
+
+    let e = conversion_error(DeriveTrack::from_node(node_map! { ("id", 1), ("hwdec", "no") }).unwrap_err());
+    assert_eq!((e.path.as_str(), e.found.as_str()), ("/type", "nothing"));
+}
+
+#[test]
+fn node_ref_views_mpv_data() {
+    let filename = CString::new("a.mkv").unwrap();
+    let entry_values = [
+        mpv_node {
+            u: mpv_node__bindgen_ty_1 { string: filename.as_ptr() as *mut c_char },
+            format: mpv_format_MPV_FORMAT_STRING,
+        },
+        mpv_node {
+            u: mpv_node__bindgen_ty_1 { flag: 1 },
+            format: mpv_format_MPV_FORMAT_FLAG,
+        },
+    ];
+    let filename_label = CString::new("filename").unwrap();
+    let current_label = CString::new("current").unwrap();
+    let labels = [filename_label.as_ptr(), current_label.as_ptr()];
+    let mut entry = mpv_node_list {
+        num: entry_values.len() as c_int,
+        values: entry_values.as_ptr() as *mut mpv_node,
+        keys: labels.as_ptr() as *mut *mut c_char,
+    };
+
+    let elements = [
+        mpv_node {
+            u: mpv_node__bindgen_ty_1 { list: &raw mut entry },
+            format: mpv_format_MPV_FORMAT_NODE_MAP,
+        },
+        mpv_node {
+            u: mpv_node__bindgen_ty_1 { int64: 7 },
+            format: mpv_format_MPV_FORMAT_INT64,
+        },
+    ];
+    let mut array = mpv_node_list {
+        num: elements.len() as c_int,
+        values: elements.as_ptr() as *mut mpv_node,
+        keys: null_mut(),
+    };
+
+    let guard = unsafe { crate::NodeGuard::from_raw(mpv_node {
+        u: mpv_node__bindgen_ty_1 { list: &raw mut array },
+        format: mpv_format_MPV_FORMAT_NODE_ARRAY,
+    }) };
+    let root = guard.get();
+
+    assert_eq!(root.len(), 2);
+    assert_eq!(root.at(1).and_then(|n| n.as_i64()), Some(7));
+    assert_eq!(root.at(1).and_then(|n| n.as_f64()), Some(7.0));
+    assert!(root.at(2).is_none());
+    assert!(root.get("filename").is_none());
+
+    let first = root.at(0).unwrap();
+    assert_eq!(first.get("filename").and_then(|n| n.as_str()), Some("a.mkv"));
+    assert_eq!(first.get("current").and_then(|n| n.as_bool()), Some(true));
+    assert!(first.get("missing").is_none());
+    assert_eq!(first.entries().map(|entry| entry.map(|(k, _)| k)).collect::<Result<Vec<_>, _>>().unwrap(), ["filename", "current"]);
+    assert_eq!(root.iter().count(), 2);
+
+    assert_eq!(first.to_owned().unwrap(), node_map! {
+        ("filename", "a.mkv"),
+        ("current", true),
+    });
+
+    assert_eq!(tests::MPV_FREE_NODE_CONTENTS_CALLS.get(), 0);
+    drop(guard);
+    assert_eq!(tests::MPV_FREE_CALLS.get(), 0);
+    assert_eq!(tests::MPV_FREE_NODE_CONTENTS_CALLS.get(), 1);
+}
+
+#[test]
+fn map_keys_must_be_valid_utf8() {
+    use crate::error::{Error, RustError};
+
+    let values = [
+        mpv_node {
+            u: mpv_node__bindgen_ty_1 { int64: 1 },
+            format: mpv_format_MPV_FORMAT_INT64,
+        },
+        mpv_node {
+            u: mpv_node__bindgen_ty_1 { int64: 2 },
+            format: mpv_format_MPV_FORMAT_INT64,
+        },
+    ];
+
+    // Two different invalid keys must not be merged into a single lossy one.
+    let e_acute = CString::new(b"\xE9".to_vec()).unwrap();
+    let e_grave = CString::new(b"\xE8".to_vec()).unwrap();
+    let invalid = [e_acute.as_ptr(), e_grave.as_ptr()];
+    let valid = CString::new("a").unwrap();
+    let null = [valid.as_ptr(), std::ptr::null()];
+
+    for keys in [invalid, null] {
+        let mut list = mpv_node_list {
+            num: values.len() as c_int,
+            values: values.as_ptr() as *mut mpv_node,
+            keys: keys.as_ptr() as *mut *mut c_char,
+        };
+
+        let result = unsafe { NodeMap::from_ptr(&raw const list as *const c_void) };
+        assert!(matches!(result, Err(Error::Rust(RustError::InvalidUtf8(_) | RustError::Pointer(_)))), "{result:?}");
+
+        let guard = unsafe { crate::NodeGuard::from_raw(mpv_node {
+            u: mpv_node__bindgen_ty_1 { list: &raw mut list },
+            format: mpv_format_MPV_FORMAT_NODE_MAP,
+        }) };
+        assert!(guard.get().to_owned().is_err());
+        assert!(guard.get().entries().any(|entry| entry.is_err()));
+    }
+
+    assert_eq!(tests::MPV_FREE_NODE_CONTENTS_CALLS.get(), 2);
+}
+
+#[test]
+fn interior_null_is_rejected() {
+    use crate::error::{Error, RustError};
+
+    let nodes = [
+        Node::String("a\0b".to_string()),
+        Node::RawString(b"a\0b".to_vec()),
+        node_map! { ("a\0b", 1) },
+        node_array!(node_map! { ("ok", "a\0b") }),
+    ];
+
+    for node in nodes {
+        let result = node.to_mpv(|_| panic!("data with an interior NUL was sent to mpv"));
+        assert!(matches!(result, Err(Error::Rust(RustError::InteriorNull(_)))), "{node:?}");
+    }
+}
+
+#[test]
+fn json_write() {
+    use crate::types::json::write;
+
+    let node = node_array!("a\"b\\c\n\u{1}", 1, 1.5, true, Node::None, node_array!());
+    assert_eq!(write(&node).unwrap(), r#"["a\"b\\c\n\u0001",1,1.500000,true,null,[]]"#);
+
+    assert_eq!(write(&Node::Double(f64::INFINITY)).unwrap(), "\"inf\"");
+    assert!(write(&Node::ByteArray(vec![1, 2, 3])).is_err());
+}
+
+#[test]
+fn json_parse() {
+    use crate::types::json::parse;
+
+    let node = parse(r#" { "a": [1, -2.5, 1e3, "xé😀",], b = null, "c": {"d": false}, } "#).unwrap();
+
+    assert_eq!(node, node_map! {
+        ("a", node_array!(1, -2.5, 1000.0, "xé😀")),
+        ("b", Node::None),
+        ("c", node_map! { ("d", false) }),
+    });
+
+    assert!(parse("[1, 2").is_err());
+    assert!(parse("[1] x").is_err());
+    assert!(parse("{\"a\" 1}").is_err());
+    assert!(parse(&"[".repeat(100)).is_err());
+}
+
+#[test]
+fn json_parse_mpv_dialect() {
+    use crate::types::json::parse;
+
+    assert_eq!(parse(r#""\x41\e\'""#).unwrap(), Node::String("A\u{1B}'".to_string()));
+    assert_eq!(parse(r#""caf\xe9""#).unwrap(), Node::RawString(b"caf\xE9".to_vec()));
+    assert!(parse(r#"{"\xe9": 1}"#).is_err());
+    assert!(parse(r#""\x4""#).is_err());
+    assert!(parse(r#""\x+4""#).is_err());
+
+    assert_eq!(parse("{_a1 = 1}").unwrap(), node_map! { ("_a1", 1) });
+    assert!(parse("{1a = 1}").is_err());
+
+    assert_eq!(parse(r#""\u0041""#).unwrap(), Node::String("A".to_string()));
+    assert!(parse(r#""\u+041""#).is_err());
+    assert!(parse(r#""\u 041""#).is_err());
+
+    assert_eq!(parse(r#""\ud83d\ude00""#).unwrap(), Node::String("😀".to_string()));
+    assert!(parse(r#""\ud83d""#).is_err());
+}
+
+#[test]
+fn json_round_trip() {
+    use crate::types::json::{parse, write};
+
+    let node = node_map! {
+        ("title", "{weird} \\ \"title\"\t"),
+        ("list", node_array!(1, 2.25, false, Node::None)),
+    };
+
+    assert_eq!(parse(&write(&node).unwrap()).unwrap(), node);
+    assert_eq!(Node::from_json_str(&node.to_json_string().unwrap()).unwrap(), node);
+
+    let raw = Node::RawString(b"a\"\xE9\xFF".to_vec());
+    assert_eq!(write(&raw).unwrap(), r#""a\"\xe9\xff""#);
+    assert_eq!(parse(&write(&raw).unwrap()).unwrap(), raw);
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum SerdeMode {
+    Auto,
+    Fixed(i64),
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct SerdeTrack {
+    id: i64,
+    title: Option<String>,
+    default_volume: f64,
+    langs: Vec<String>,
+    cover: Vec<u8>,
+    mode: SerdeMode,
+    other_mode: SerdeMode,
+}
+
+#[cfg(feature = "serde")]
+fn serde_track() -> SerdeTrack {
+    SerdeTrack {
+        id: 2,
+        title: None,
+        default_volume: 0.5,
+        langs: vec!["en".to_string(), "de".to_string()],
+        cover: vec![0xFF, 0xD8],
+        mode: SerdeMode::Auto,
+        other_mode: SerdeMode::Fixed(3),
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_to_node() {
+    use crate::to_node;
+
+    assert_eq!(to_node(&serde_track()).unwrap(), node_map! {
+        ("id", 2),
+        ("title", Node::None),
+        ("default-volume", 0.5),
+        ("langs", node_array!("en", "de")),
+        ("cover", Node::ByteArray(vec![0xFF, 0xD8])),
+        ("mode", "auto"),
+        ("other-mode", node_map! { ("fixed", 3) }),
+    });
+
+    assert_eq!(to_node(&(1u8, 2u8)).unwrap(), node_array!(1, 2));
+    assert!(to_node(&u64::MAX).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_from_node() {
+    use crate::{from_node, to_node};
+
+    assert_eq!(from_node::<SerdeTrack>(to_node(&serde_track()).unwrap()).unwrap(), serde_track());
+
+    // Missing `Option` fields and extra keys are fine, as with any serde format.
+    let node = node_map! {
+        ("id", 1), ("default-volume", 1), ("langs", node_array!()), ("cover", node_array!(1)),
+        ("mode", "auto"), ("other-mode", "auto"), ("unknown", true),
+    };
+    assert_eq!(from_node::<SerdeTrack>(node).unwrap().cover, vec![1]);
+
+    assert!(from_node::<SerdeTrack>(Node::Int64(1)).is_err());
+    assert_eq!(from_node::<Node>(node_array!(1, "a")).unwrap(), node_array!(1, "a"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_value_from_node() {
+    use serde_json::{json, Value};
+    use crate::ByteArrayEncoding;
+    use crate::types::json_value::base64;
+
+    let node = node_map! {
+        ("int", 1),
+        ("double", 1.0),
+        ("inf", f64::INFINITY),
+        ("list", node_array!("a", true, Node::None)),
+        ("bytes", Node::ByteArray(b"hello".to_vec())),
+    };
+
+    assert_eq!(Value::from(&node), json!({
+        "int": 1, "double": 1.0, "inf": "inf", "list": ["a", true, null], "bytes": [104, 101, 108, 108, 111],
+    }));
+    assert_eq!(node.to_json_value(ByteArrayEncoding::Base64)["bytes"], json!("aGVsbG8="));
+    assert_eq!(base64(b""), "");
+    assert_eq!(base64(b"ab"), "YWI=");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_value_to_node() {
+    use serde_json::{json, Value};
+
+    let node = Node::try_from(json!({ "int": 1, "double": 1.0, "list": [-2, 2.5, "x"] })).unwrap();
+
+    assert_eq!(node, node_map! {
+        ("int", 1),
+        ("double", 1.0),
+        ("list", node_array!(-2, 2.5, "x")),
+    });
+    assert_eq!(Node::try_from(Value::from(&node)).unwrap(), node);
+    assert!(Node::try_from(json!(u64::MAX)).is_err());
+}
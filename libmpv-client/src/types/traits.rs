@@ -73,8 +73,11 @@ pub(crate) trait MpvRecvInternal: MpvFormat {
 pub(crate) trait ToMpvRepr: MpvSend {
     type ReprWrap<'a>: MpvRepr where Self: 'a;
 
-    // TODO: Make this return a Result<> for better error handling.
-    fn to_mpv_repr(&self) -> Self::ReprWrap<'_>;
+    /// Build the mpv representation of this value, borrowing from it where possible.
+    ///
+    /// Fails with [`RustError::InteriorNull`](crate::error::RustError::InteriorNull) if a string or map key contains a NUL byte,
+    /// since mpv would otherwise silently receive a truncated or empty string.
+    fn to_mpv_repr(&self) -> Result<Self::ReprWrap<'_>>;
 }
 
 pub(crate) trait MpvRepr: Sized {